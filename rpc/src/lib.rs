@@ -0,0 +1,327 @@
+//! RPC interface for the tablescore pallet
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use tablescore::runtime_api::TablescoreApi as TablescoreRuntimeApi;
+
+#[rpc]
+pub trait TablescoreApi<BlockHash, TableId, TargetType, AccountId, AssetId, Balance> {
+    /// Targets with the biggest vote-balance in table
+    #[rpc(name = "tablescore_getHead")]
+    fn get_head(&self, table_id: TableId, at: Option<BlockHash>) -> Result<Vec<TargetType>>;
+
+    /// Total vote-balance of target
+    #[rpc(name = "tablescore_getScore")]
+    fn get_score(
+        &self,
+        table_id: TableId,
+        target: TargetType,
+        at: Option<BlockHash>,
+    ) -> Result<Option<Balance>>;
+
+    /// Place of target in table, the leader has rank 1
+    #[rpc(name = "tablescore_getRank")]
+    fn get_rank(
+        &self,
+        table_id: TableId,
+        target: TargetType,
+        at: Option<BlockHash>,
+    ) -> Result<Option<u32>>;
+
+    /// Vote-balances of account for every target it voted for in every table
+    #[rpc(name = "tablescore_getVotesOfAccount")]
+    fn get_votes_of_account(
+        &self,
+        voter: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<(TableId, TargetType, Balance)>>;
+
+    /// Rewards by asset that voter can pick up for target
+    #[rpc(name = "tablescore_pendingReward")]
+    fn pending_reward(
+        &self,
+        table_id: TableId,
+        target: TargetType,
+        voter: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<(AssetId, Balance)>>;
+
+    /// Reward in asset of removed targets that no voter can pick up
+    #[rpc(name = "tablescore_getDust")]
+    fn get_dust(&self, table_id: TableId, asset: AssetId, at: Option<BlockHash>) -> Result<Balance>;
+}
+
+/// Tablescore RPC methods on top of the runtime API
+pub struct Tablescore<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Tablescore<C, B> {
+    pub fn new(client: Arc<C>) -> Self {
+        Tablescore {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+/// Error type of this RPC api
+pub enum Error {
+    /// Call to the runtime failed
+    RuntimeError,
+}
+
+impl From<Error> for i64 {
+    fn from(e: Error) -> i64 {
+        match e {
+            Error::RuntimeError => 1,
+        }
+    }
+}
+
+fn runtime_error<E: std::fmt::Debug>(message: &str, error: E) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(Error::RuntimeError.into()),
+        message: message.into(),
+        data: Some(format!("{:?}", error).into()),
+    }
+}
+
+impl<C, Block, TableId, TargetType, AccountId, AssetId, Balance>
+    TablescoreApi<<Block as BlockT>::Hash, TableId, TargetType, AccountId, AssetId, Balance>
+    for Tablescore<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: TablescoreRuntimeApi<Block, TableId, TargetType, AccountId, AssetId, Balance>,
+    TableId: Codec,
+    TargetType: Codec,
+    AccountId: Codec,
+    AssetId: Codec,
+    Balance: Codec,
+{
+    fn get_head(
+        &self,
+        table_id: TableId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<TargetType>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client
+            .runtime_api()
+            .head(&at, table_id)
+            .map_err(|e| runtime_error("Unable to query table head.", e))
+    }
+
+    fn get_score(
+        &self,
+        table_id: TableId,
+        target: TargetType,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<Balance>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client
+            .runtime_api()
+            .score_of(&at, table_id, target)
+            .map_err(|e| runtime_error("Unable to query target score.", e))
+    }
+
+    fn get_rank(
+        &self,
+        table_id: TableId,
+        target: TargetType,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<u32>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client
+            .runtime_api()
+            .rank_of(&at, table_id, target)
+            .map_err(|e| runtime_error("Unable to query target rank.", e))
+    }
+
+    fn get_votes_of_account(
+        &self,
+        voter: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<(TableId, TargetType, Balance)>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client
+            .runtime_api()
+            .votes_of_account(&at, voter)
+            .map_err(|e| runtime_error("Unable to query votes of account.", e))
+    }
+
+    fn pending_reward(
+        &self,
+        table_id: TableId,
+        target: TargetType,
+        voter: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<(AssetId, Balance)>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client
+            .runtime_api()
+            .pending_reward(&at, table_id, target, voter)
+            .map_err(|e| runtime_error("Unable to query pending reward.", e))
+    }
+
+    fn get_dust(
+        &self,
+        table_id: TableId,
+        asset: AssetId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Balance> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        self.client
+            .runtime_api()
+            .dust(&at, table_id, asset)
+            .map_err(|e| runtime_error("Unable to query table dust.", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_api::{ApiRef, ProvideRuntimeApi};
+    use sp_blockchain::{BlockStatus, Info};
+    use sp_core::H256;
+    use sp_runtime::{
+        testing::{Block as TestBlock, ExtrinsicWrapper, Header},
+        traits::NumberFor,
+    };
+
+    type Block = TestBlock<ExtrinsicWrapper<u64>>;
+
+    const LEADER: u32 = 7;
+    const VOTER: u64 = 1;
+    const ASSET: u32 = 0;
+
+    #[derive(Default)]
+    struct TestApi;
+
+    #[derive(Default, Clone)]
+    struct RuntimeApi;
+
+    impl ProvideRuntimeApi<Block> for TestApi {
+        type Api = RuntimeApi;
+
+        fn runtime_api<'a>(&'a self) -> ApiRef<'a, Self::Api> {
+            RuntimeApi.into()
+        }
+    }
+
+    impl HeaderBackend<Block> for TestApi {
+        fn header(&self, _id: BlockId<Block>) -> sp_blockchain::Result<Option<Header>> {
+            Ok(None)
+        }
+
+        fn info(&self) -> Info<Block> {
+            Info {
+                best_hash: Default::default(),
+                best_number: 0,
+                genesis_hash: Default::default(),
+                finalized_hash: Default::default(),
+                finalized_number: 0,
+            }
+        }
+
+        fn status(&self, _id: BlockId<Block>) -> sp_blockchain::Result<BlockStatus> {
+            Ok(BlockStatus::Unknown)
+        }
+
+        fn number(&self, _hash: H256) -> sp_blockchain::Result<Option<NumberFor<Block>>> {
+            Ok(None)
+        }
+
+        fn hash(&self, _number: NumberFor<Block>) -> sp_blockchain::Result<Option<H256>> {
+            Ok(None)
+        }
+    }
+
+    sp_api::mock_impl_runtime_apis! {
+        impl TablescoreRuntimeApi<Block, u32, u32, u64, u32, u128> for RuntimeApi {
+            fn wallet(_table_id: u32) -> Option<u64> {
+                None
+            }
+
+            fn head(table_id: u32) -> Vec<u32> {
+                if table_id == 0 { vec![LEADER, 3] } else { vec![] }
+            }
+
+            fn score_of(table_id: u32, target: u32) -> Option<u128> {
+                if table_id == 0 && target == LEADER { Some(100) } else { None }
+            }
+
+            fn rank_of(table_id: u32, target: u32) -> Option<u32> {
+                if table_id == 0 && target == LEADER { Some(1) } else { None }
+            }
+
+            fn vote_of(_table_id: u32, _target: u32, _voter: u64) -> Option<u128> {
+                None
+            }
+
+            fn votes_of_account(voter: u64) -> Vec<(u32, u32, u128)> {
+                if voter == VOTER { vec![(0, LEADER, 100)] } else { vec![] }
+            }
+
+            fn pending_reward(table_id: u32, target: u32, voter: u64) -> Vec<(u32, u128)> {
+                if table_id == 0 && target == LEADER && voter == VOTER {
+                    vec![(ASSET, 42), (ASSET + 1, 7)]
+                } else {
+                    vec![]
+                }
+            }
+
+            fn dust(table_id: u32, asset: u32) -> u128 {
+                if table_id == 0 && asset == ASSET { 3 } else { 0 }
+            }
+        }
+    }
+
+    fn rpc() -> impl TablescoreApi<H256, u32, u32, u64, u32, u128> {
+        Tablescore::new(Arc::new(TestApi))
+    }
+
+    #[test]
+    fn get_head() {
+        assert_eq!(rpc().get_head(0, None).unwrap(), vec![LEADER, 3]);
+        assert_eq!(rpc().get_head(1, Some(H256::repeat_byte(1))).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn get_score_and_rank() {
+        assert_eq!(rpc().get_score(0, LEADER, None).unwrap(), Some(100));
+        assert_eq!(rpc().get_score(0, 3, None).unwrap(), None);
+        assert_eq!(rpc().get_rank(0, LEADER, None).unwrap(), Some(1));
+        assert_eq!(rpc().get_rank(1, LEADER, None).unwrap(), None);
+    }
+
+    #[test]
+    fn get_votes_of_account() {
+        assert_eq!(rpc().get_votes_of_account(VOTER, None).unwrap(), vec![(0, LEADER, 100)]);
+        assert_eq!(rpc().get_votes_of_account(VOTER + 1, None).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn pending_reward() {
+        assert_eq!(
+            rpc().pending_reward(0, LEADER, VOTER, None).unwrap(),
+            vec![(ASSET, 42), (ASSET + 1, 7)]
+        );
+        assert_eq!(rpc().pending_reward(0, LEADER, VOTER + 1, None).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn get_dust() {
+        assert_eq!(rpc().get_dust(0, ASSET, None).unwrap(), 3);
+        assert_eq!(rpc().get_dust(0, ASSET + 1, None).unwrap(), 0);
+        assert_eq!(rpc().get_dust(1, ASSET, None).unwrap(), 0);
+    }
+}
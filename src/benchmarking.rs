@@ -0,0 +1,467 @@
+//! Benchmarks for tablescore dispatchables
+
+use super::*;
+
+use codec::{Decode, Encode};
+use frame_benchmarking::{account, benchmarks};
+use frame_support::storage::unhashed;
+use rstd::prelude::*;
+use system::RawOrigin;
+
+const SEED: u32 = 0;
+const MAX_NAME: u32 = 256;
+const MAX_TARGETS: u32 = 1_000;
+const MAX_PERIODS: u32 = 100;
+const MAX_ASSETS: u32 = 16;
+const MAX_HEAD: u32 = 255;
+const MAX_CLAIM_TABLES: u32 = 10;
+const MAX_ADMINS: u32 = 32;
+const MAX_VOTERS: u32 = 1_000;
+const VOTE: u32 = 1_000;
+
+/// Distinct target for every index
+fn target<T: Trait>(index: u32) -> T::TargetType {
+    let mut bytes = index.encode();
+    bytes.resize(128, 0);
+    T::TargetType::decode(&mut &bytes[..]).unwrap_or_default()
+}
+
+fn funded_account<T: Trait>(name: &'static str, index: u32, asset: &AssetId<T>) -> AccountId<T> {
+    let who = account(name, index, SEED);
+    assets::Module::<T>::set_free_balance(asset, &who, Balance::<T>::from(u32::max_value()));
+    assets::Module::<T>::set_free_balance(&T::DepositAsset::get(), &who, Balance::<T>::from(u32::max_value()));
+    who
+}
+
+/// Payment of reward stream that passes `MinStreamAmount`
+fn stream_amount<T: Trait>() -> Balance<T> {
+    Balance::<T>::from(VOTE).max(T::MinStreamAmount::get())
+}
+
+/// Table with `t` targets where the first target has `p` reward periods
+/// and pending reward in `a` assets for `caller`
+fn setup_table<T: Trait>(caller: &AccountId<T>, t: u32, p: u32, a: u32) -> T::TableId {
+    setup_table_with_head::<T>(caller, t, T::MaxHeadLen::get(), p, a)
+}
+
+/// The same as `setup_table` with `head_len` targets in head.
+///
+/// Components are clamped to limits of the pallet: the first target takes
+/// at most `MaxVotersPerTarget` voters, one of them is `caller` and one
+/// is the voter of the first target.
+fn setup_table_with_head<T: Trait>(caller: &AccountId<T>, t: u32, head_len: u8, p: u32, a: u32) -> T::TableId {
+    let t = t.min(T::MaxTargetsPerTable::get());
+    let p = p.min(T::MaxVotersPerTarget::get().saturating_sub(2));
+    let asset = AssetId::<T>::default();
+    let owner = funded_account::<T>("owner", 0, &asset);
+    let table_id = Module::<T>::create(owner.clone(), asset.clone(), head_len.min(T::MaxHeadLen::get()), None, None)
+        .expect("owner is funded");
+
+    for i in 0..t {
+        let voter = funded_account::<T>("voter", i, &asset);
+        Module::<T>::vote(
+            RawOrigin::Signed(voter).into(),
+            table_id,
+            Balance::<T>::from(VOTE + i),
+            target::<T>(i),
+        )
+        .expect("voter is funded");
+    }
+
+    // Every voter after a reward opens a new reward period
+    for i in 0..p {
+        Module::<T>::append_reward(
+            RawOrigin::Signed(owner.clone()).into(),
+            table_id,
+            Balance::<T>::from(VOTE),
+            target::<T>(0),
+            Some(asset.clone()),
+        )
+        .expect("owner is funded");
+        let voter = funded_account::<T>("period", i, &asset);
+        Module::<T>::vote(
+            RawOrigin::Signed(voter).into(),
+            table_id,
+            Balance::<T>::from(VOTE),
+            target::<T>(0),
+        )
+        .expect("voter is funded");
+    }
+
+    Module::<T>::vote(
+        RawOrigin::Signed(caller.clone()).into(),
+        table_id,
+        Balance::<T>::from(VOTE),
+        target::<T>(0),
+    )
+    .expect("caller is funded");
+    for i in 0..a.min(T::MaxRewardAssets::get()) {
+        let reward_asset = AssetId::<T>::from(i);
+        assets::Module::<T>::set_free_balance(&reward_asset, &owner, Balance::<T>::from(u32::max_value()));
+        Module::<T>::append_reward(
+            RawOrigin::Signed(owner.clone()).into(),
+            table_id,
+            Balance::<T>::from(VOTE * (t + p + 1)),
+            target::<T>(0),
+            Some(reward_asset),
+        )
+        .expect("owner is funded");
+    }
+
+    table_id
+}
+
+/// Table of `index` owner where `caller` voted for `t` targets and every target
+/// is rewarded in `a` assets
+fn setup_claim<T: Trait>(caller: &AccountId<T>, index: u32, t: u32, a: u32) -> T::TableId {
+    let asset = AssetId::<T>::default();
+    let owner = funded_account::<T>("owner", index, &asset);
+    let table_id = Module::<T>::create(owner.clone(), asset, T::MaxHeadLen::get(), None, None)
+        .expect("owner is funded");
+
+    for i in 0..t.min(T::MaxTargetsPerTable::get()) {
+        Module::<T>::vote(
+            RawOrigin::Signed(caller.clone()).into(),
+            table_id,
+            Balance::<T>::from(VOTE),
+            target::<T>(i),
+        )
+        .expect("caller is funded");
+
+        for j in 0..a.min(T::MaxRewardAssets::get()) {
+            let reward_asset = AssetId::<T>::from(j);
+            assets::Module::<T>::set_free_balance(&reward_asset, &owner, Balance::<T>::from(u32::max_value()));
+            Module::<T>::append_reward(
+                RawOrigin::Signed(owner.clone()).into(),
+                table_id,
+                Balance::<T>::from(VOTE),
+                target::<T>(i),
+                Some(reward_asset),
+            )
+            .expect("owner is funded");
+        }
+    }
+
+    table_id
+}
+
+benchmarks! {
+    _ {
+        let t in 1 .. MAX_TARGETS => ();
+        let p in 0 .. MAX_PERIODS => ();
+        let a in 1 .. MAX_ASSETS => ();
+    }
+
+    create_table {
+        let n in 0 .. MAX_NAME;
+        let asset = AssetId::<T>::default();
+        let caller = T::CreateTableOrigin::ensure_origin(T::CreateTableOrigin::successful_origin())
+            .unwrap_or_else(|_| panic!("successful origin passes"));
+        assets::Module::<T>::set_free_balance(
+            &T::DepositAsset::get(),
+            &caller,
+            Balance::<T>::from(u32::max_value()),
+        );
+        let name = vec![0; n.min(T::MaxNameLength::get()) as usize];
+    }: _(T::CreateTableOrigin::successful_origin(), asset, T::MaxHeadLen::get(), Some(name), None)
+
+    vote {
+        let t in ...;
+        let p in ...;
+        let a in ...;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, t, p, a);
+    }: _(RawOrigin::Signed(caller), table_id, Balance::<T>::from(VOTE * (t + 1)), target::<T>(0))
+
+    unvote {
+        let t in ...;
+        let p in ...;
+        let a in ...;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, t, p, a);
+    }: _(RawOrigin::Signed(caller), table_id, Balance::<T>::from(VOTE / 2), target::<T>(0))
+
+    cancel {
+        let t in ...;
+        let p in ...;
+        let a in ...;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, t, p, a);
+    }: _(RawOrigin::Signed(caller), table_id, target::<T>(0))
+
+    append_reward {
+        let t in ...;
+        let p in ...;
+        let a in ...;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, t, p, a);
+    }: _(RawOrigin::Signed(caller), table_id, Balance::<T>::from(VOTE), target::<T>(0), Some(asset))
+
+    append_head_reward {
+        let t in ...;
+        let h in 1 .. MAX_HEAD;
+        let p in ...;
+        let a in ...;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let h = h.min(T::MaxHeadLen::get() as u32);
+        let table_id = setup_table_with_head::<T>(&caller, t.max(h), h as u8, p, a);
+    }: _(RawOrigin::Signed(caller), table_id, Balance::<T>::from(VOTE), HeadRewardPolicy::Proportional)
+
+    pop_reward {
+        let t in ...;
+        let p in ...;
+        let a in ...;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, t, p, a);
+    }: _(RawOrigin::Signed(caller), table_id, target::<T>(0))
+
+    claim_all {
+        let n in 1 .. MAX_CLAIM_TABLES;
+        let t in ...;
+        let a in ...;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_ids: Vec<T::TableId> = (0..n).map(|i| setup_claim::<T>(&caller, i, t, a)).collect();
+    }: _(RawOrigin::Signed(caller), table_ids)
+
+    sweep_dust {
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, 1, 0, 1);
+        Module::<T>::cancel(RawOrigin::Signed(caller.clone()).into(), table_id, target::<T>(0))
+            .expect("caller voted");
+        Module::<T>::cancel(RawOrigin::Signed(account("voter", 0, SEED)).into(), table_id, target::<T>(0))
+            .expect("voter voted");
+    }: _(RawOrigin::Root, table_id, asset, caller)
+
+    create_reward_stream {
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, 1, 0, 1);
+        let now = system::Module::<T>::block_number();
+
+        // The new stream is the last one table can have
+        for _ in 1..T::MaxStreamsPerTable::get() {
+            Module::<T>::create_reward_stream(
+                RawOrigin::Signed(caller.clone()).into(),
+                table_id,
+                StreamRecipient::Target(target::<T>(0)),
+                stream_amount::<T>(),
+                T::BlockNumber::from(10u32),
+                now + T::BlockNumber::from(100u32),
+            )
+            .expect("caller is funded");
+        }
+    }: _(
+        RawOrigin::Signed(caller),
+        table_id,
+        StreamRecipient::Target(target::<T>(0)),
+        stream_amount::<T>(),
+        T::BlockNumber::from(10u32),
+        now + T::BlockNumber::from(100u32)
+    )
+
+    cancel_reward_stream {
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, 1, 0, 1);
+        let now = system::Module::<T>::block_number();
+        let stream_id = Module::<T>::next_stream_id();
+        Module::<T>::create_reward_stream(
+            RawOrigin::Signed(caller.clone()).into(),
+            table_id,
+            StreamRecipient::Target(target::<T>(0)),
+            stream_amount::<T>(),
+            T::BlockNumber::from(10u32),
+            now + T::BlockNumber::from(100u32),
+        )
+        .expect("caller is funded");
+    }: _(RawOrigin::Signed(caller), stream_id)
+
+    set_table_state {
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, 1, 0, 1);
+        let owner: AccountId<T> = account("owner", 0, SEED);
+    }: destroy_table(RawOrigin::Signed(owner), table_id)
+
+    transfer_ownership {
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, 1, 0, 1);
+        let owner: AccountId<T> = account("owner", 0, SEED);
+    }: _(RawOrigin::Signed(owner), table_id, caller)
+
+    set_admins {
+        let n in 0 .. MAX_ADMINS;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, 1, 0, 1);
+        let owner: AccountId<T> = account("owner", 0, SEED);
+        let admins: Vec<AccountId<T>> = (0..n.min(T::MaxTableAdmins::get()))
+            .map(|i| account("admin", i, SEED))
+            .collect();
+    }: _(RawOrigin::Signed(owner), table_id, admins)
+
+    set_name {
+        let n in 0 .. MAX_NAME;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, 1, 0, 1);
+        let owner: AccountId<T> = account("owner", 0, SEED);
+        let name = vec![0; n.min(T::MaxNameLength::get()) as usize];
+    }: _(RawOrigin::Signed(owner), table_id, Some(name))
+
+    set_head_len {
+        let t in ...;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table_with_head::<T>(&caller, t, 1, 0, 1);
+        let owner: AccountId<T> = account("owner", 0, SEED);
+    }: _(RawOrigin::Signed(owner), table_id, T::MaxHeadLen::get())
+
+    set_registration {
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, 1, 0, 1);
+        let owner: AccountId<T> = account("owner", 0, SEED);
+    }: _(RawOrigin::Signed(owner), table_id, Some(Balance::<T>::from(VOTE)))
+
+    register_target {
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = Module::<T>::create(caller.clone(), asset, T::MaxHeadLen::get(), None, None)
+            .expect("caller is funded");
+        Module::<T>::set_registration(
+            RawOrigin::Signed(caller.clone()).into(),
+            table_id,
+            Some(Balance::<T>::from(VOTE)),
+        )
+        .expect("caller is owner");
+    }: _(RawOrigin::Signed(caller), table_id, target::<T>(0))
+
+    deregister_target {
+        let t in ...;
+        let v in 1 .. MAX_VOTERS;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = Module::<T>::create(caller.clone(), asset.clone(), T::MaxHeadLen::get(), None, None)
+            .expect("caller is funded");
+        Module::<T>::set_registration(
+            RawOrigin::Signed(caller.clone()).into(),
+            table_id,
+            Some(Balance::<T>::from(VOTE)),
+        )
+        .expect("caller is owner");
+
+        for i in 0..t.min(T::MaxTargetsPerTable::get()) {
+            Module::<T>::register_target(RawOrigin::Signed(caller.clone()).into(), table_id, target::<T>(i))
+                .expect("caller is funded");
+            Module::<T>::vote(
+                RawOrigin::Signed(caller.clone()).into(),
+                table_id,
+                Balance::<T>::from(VOTE + i),
+                target::<T>(i),
+            )
+            .expect("caller is funded");
+        }
+        for i in 1..v.min(T::MaxVotersPerTarget::get()) {
+            let voter = funded_account::<T>("voter", i, &asset);
+            Module::<T>::vote(
+                RawOrigin::Signed(voter).into(),
+                table_id,
+                Balance::<T>::from(VOTE),
+                target::<T>(0),
+            )
+            .expect("voter is funded");
+        }
+    }: _(RawOrigin::Signed(caller), table_id, target::<T>(0))
+
+    set_voter_policy {
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, 1, 0, 1);
+        let owner: AccountId<T> = account("owner", 0, SEED);
+    }: _(RawOrigin::Signed(owner), table_id, VoterPolicy::AllowList)
+
+    allow_voters {
+        let n in 0 .. MAX_VOTERS;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, 1, 0, 1);
+        let owner: AccountId<T> = account("owner", 0, SEED);
+        let voters: Vec<AccountId<T>> = (0..n).map(|i| account("voter", i, SEED)).collect();
+    }: _(RawOrigin::Signed(owner), table_id, voters)
+
+    disallow_voters {
+        let n in 0 .. MAX_VOTERS;
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = setup_table::<T>(&caller, 1, 0, 1);
+        let owner: AccountId<T> = account("owner", 0, SEED);
+        let voters: Vec<AccountId<T>> = (0..n).map(|i| account("voter", i, SEED)).collect();
+        Module::<T>::allow_voters(RawOrigin::Signed(owner.clone()).into(), table_id, voters.clone())
+            .expect("owner of table");
+    }: _(RawOrigin::Signed(owner), table_id, voters)
+
+    migrate_table {
+        let t in ...;
+        let v in 1 .. MAX_VOTERS;
+        let asset = AssetId::<T>::default();
+        let owner = funded_account::<T>("owner", 0, &asset);
+        let table_id = TableIdSequence::<T>::get();
+        let mut table = migration::TableV0::<T>::default();
+        table.vote_asset = asset;
+        table.wallet = owner;
+
+        // Votes of the first target are spread over `t` reward periods
+        for i in 0..t {
+            let votes = if i == 0 { v } else { 1 };
+            let mut data = migration::v0::TargetData::default();
+            for j in 0..votes {
+                let voter: AccountId<T> = account("voter", i * MAX_VOTERS + j, SEED);
+                data.votes.insert(voter.clone(), Balance::<T>::from(VOTE));
+                data.rewarder.origin.insert(voter, T::PeriodType::from(j % t));
+                data.total += Balance::<T>::from(VOTE);
+            }
+            for period in 0..t.min(votes) {
+                data.rewarder.rewards.insert(T::PeriodType::from(period), Balance::<T>::from(VOTE));
+            }
+            table.scores.insert(Record::new(target::<T>(i), data.total));
+            table.targets.insert(target::<T>(i), data);
+        }
+        unhashed::put(&migration::v0_table_key::<T>(table_id), &table);
+    }: {
+        migration::migrate_table::<T>(table_id);
+    }
+
+    remove_table {
+        let s in 0 .. T::MaxStreamsPerTable::get();
+        let asset = AssetId::<T>::default();
+        let caller = funded_account::<T>("caller", 0, &asset);
+        let table_id = Module::<T>::create(caller.clone(), asset, T::MaxHeadLen::get(), None, None)
+            .expect("caller is funded");
+
+        let now = system::Module::<T>::block_number();
+        for _ in 0..s {
+            Module::<T>::create_reward_stream(
+                RawOrigin::Signed(caller.clone()).into(),
+                table_id,
+                StreamRecipient::Target(target::<T>(0)),
+                stream_amount::<T>(),
+                T::BlockNumber::from(10u32),
+                now + T::BlockNumber::from(100u32),
+            )
+            .expect("caller is funded");
+        }
+        Module::<T>::destroy_table(RawOrigin::Signed(caller).into(), table_id).expect("caller is owner");
+    }: {
+        Module::<T>::remove_table(table_id);
+    }
+}
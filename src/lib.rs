@@ -1,18 +1,47 @@
 #![feature(map_first_last)]
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, dispatch, Parameter};
-use sp_arithmetic::traits::{SimpleArithmetic, CheckedAdd, One};
-use sp_runtime::traits::Member;
+use codec::{Decode, Encode, FullCodec, FullEncode};
+use frame_support::{
+    decl_error, decl_event, decl_module, decl_storage, dispatch, ensure,
+    storage::generator,
+    traits::{Contains, EnsureOrigin, Get},
+    weights::{DispatchClass, FunctionOf, SimpleDispatchInfo, Weight},
+    Parameter,
+};
+use sp_arithmetic::traits::{
+    CheckedAdd, CheckedMul, One, Saturating, SimpleArithmetic, UniqueSaturatedInto, Zero,
+};
+use sp_runtime::{
+    traits::{AccountIdConversion, Convert, Member},
+    ModuleId,
+};
+use sp_io::hashing::{blake2_256, twox_128};
 use system::ensure_signed;
 
+use crate::migration::StorageVersion;
+use crate::record::Record;
+use crate::table::Limits;
+pub use crate::reward_stream::{StreamId, StreamRecipient};
+pub use crate::table::{HeadRewardPolicy, TableState, VoterPolicy};
 use crate::table_data::VoteResult;
-use rstd::prelude::Vec;
+use crate::weights::WeightInfo;
+use rstd::{
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+    prelude::Vec,
+};
 
+pub mod migration;
 mod record;
 mod reward_sharing;
+mod reward_stream;
+pub mod runtime_api;
 mod table;
 mod table_data;
+pub mod weights;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 
 #[cfg(test)]
 mod mock;
@@ -29,6 +58,78 @@ pub trait Trait: system::Trait + assets::Trait {
 
     /// Target for vote
     type TargetType: Default + Parameter + Ord + Clone;
+
+    /// Id for derive wallets of tables
+    type ModuleId: Get<ModuleId>;
+
+    /// Handler for changes of table head
+    type OnHeadChange: OnHeadChange<Self::TableId, Self::TargetType>;
+
+    /// Weights of dispatchables
+    type WeightInfo: WeightInfo;
+
+    /// Max count of targets with votes in one table
+    type MaxTargetsPerTable: Get<u32>;
+
+    /// Max count of voters for one target
+    type MaxVotersPerTarget: Get<u32>;
+
+    /// Max length of table name
+    type MaxNameLength: Get<u32>;
+
+    /// Max count of targets in table head
+    type MaxHeadLen: Get<u8>;
+
+    /// Max count of assets in which one target is rewarded
+    type MaxRewardAssets: Get<u32>;
+
+    /// Max count of reward stream payments in one block, the rest are moved to next blocks
+    type MaxStreamsPerBlock: Get<u32>;
+
+    /// Max count of active reward streams of one table
+    type MaxStreamsPerTable: Get<u32>;
+
+    /// Min payment of reward stream
+    type MinStreamAmount: Get<Self::Balance>;
+
+    /// Max count of votes cancelled in one block while destroying tables
+    type MaxDestroyVotes: Get<u32>;
+
+    /// Max count of tables migrated in one block on storage upgrade
+    type MaxMigrationTables: Get<u32>;
+
+    /// Origin that can administer any table besides its owner
+    type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+    /// Max count of admins of one table
+    type MaxTableAdmins: Get<u32>;
+
+    /// Origin that can create tables, it gives the creator of table
+    type CreateTableOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
+
+    /// Asset of table deposit
+    type DepositAsset: Get<Self::AssetId>;
+
+    /// Deposit reserved from creator of table until the table is destroyed
+    type TableDeposit: Get<Self::Balance>;
+
+    /// Max count of tables created by one account and not destroyed yet
+    type MaxTablesPerAccount: Get<u32>;
+
+    /// Target that account can register as itself, `()` gives `None` for every account
+    type AccountTarget: Convert<Self::AccountId, Option<Self::TargetType>>;
+
+    /// Members that can vote in tables with `VoterPolicy::Members`
+    type VoterFilter: Contains<Self::AccountId>;
+}
+
+/// Handler for targets that enter or leave head of table
+pub trait OnHeadChange<TableId, TargetType> {
+    fn on_head_change(table_id: TableId, entered: &[TargetType], left: &[TargetType]);
+}
+
+impl<TableId, TargetType> OnHeadChange<TableId, TargetType> for () {
+    fn on_head_change(_table_id: TableId, _entered: &[TargetType], _left: &[TargetType]) {}
 }
 
 type AssetId<T> = <T as assets::Trait>::AssetId;
@@ -44,13 +145,103 @@ type Table<T> = crate::table::Table<
     <T as system::Trait>::AccountId,
 >;
 
+type TableInfo<T> = crate::table::TableInfo<AssetId<T>, AccountId<T>, AccountId<T>>;
+type TargetData<T> =
+    crate::table_data::TargetData<AccountId<T>, Balance<T>, <T as Trait>::PeriodType, AssetId<T>>;
+type TargetSummary<T> =
+    crate::table_data::TargetSummary<AccountId<T>, Balance<T>, <T as Trait>::PeriodType, AssetId<T>>;
+type Rewards<T> = crate::table_data::Rewards<AssetId<T>, Balance<T>>;
+type RewardStream<T> = crate::reward_stream::RewardStream<
+    <T as Trait>::TableId,
+    <T as Trait>::TargetType,
+    AccountId<T>,
+    AssetId<T>,
+    Balance<T>,
+    <T as system::Trait>::BlockNumber,
+>;
+type ScoreSet<T> = BTreeSet<Record<<T as Trait>::TargetType, Balance<T>>>;
+
 decl_storage! {
     trait Store for Module<T: Trait> as TemplateModule {
-        /// Tables by id
-        pub Scores get(fn tables): map hasher(blake2_256) T::TableId => Table<T>;
+        /// Table settings by id
+        pub Tables get(fn tables): map hasher(blake2_256) T::TableId => Option<TableInfo<T>>;
+
+        /// Sorted target records of table for look at head
+        pub TableScores get(fn scores): map hasher(blake2_256) T::TableId => ScoreSet<T>;
+
+        /// Total vote-balance and reward periods of table target
+        pub Targets get(fn targets):
+            double_map hasher(blake2_256) T::TableId, hasher(blake2_128_concat) T::TargetType
+            => Option<TargetSummary<T>>;
+
+        /// Vote-balance of voter for table target
+        pub Votes get(fn votes):
+            double_map hasher(blake2_256) (T::TableId, T::TargetType), hasher(blake2_128_concat) AccountId<T>
+            => Option<Balance<T>>;
+
+        /// Targets of table for which account has vote
+        pub VotesByAccount get(fn votes_by_account):
+            double_map hasher(blake2_256) AccountId<T>, hasher(blake2_128_concat) T::TableId
+            => BTreeSet<T::TargetType>;
+
+        /// Reward period from which voter reward for table target is counted
+        pub Checkpoints get(fn checkpoints):
+            double_map hasher(blake2_256) (T::TableId, T::TargetType), hasher(blake2_128_concat) AccountId<T>
+            => Option<T::PeriodType>;
+
+        /// Unpaid reward of targets removed from table by asset, it's kept in table wallet
+        pub TableDust get(fn dust):
+            double_map hasher(blake2_256) T::TableId, hasher(blake2_128_concat) AssetId<T>
+            => Balance<T>;
 
         /// Sequence for table id
         TableIdSequence get(fn next_table_id): T::TableId;
+
+        /// Active reward streams by id
+        pub RewardStreams get(fn reward_streams): map hasher(blake2_256) StreamId => Option<RewardStream<T>>;
+
+        /// Reward streams to pay at block
+        pub StreamsDue get(fn streams_due): map hasher(blake2_256) T::BlockNumber => Vec<StreamId>;
+
+        /// Sequence for reward stream id
+        StreamIdSequence get(fn next_stream_id): StreamId;
+
+        /// Active reward streams of table
+        pub TableStreams get(fn table_streams): map hasher(blake2_256) T::TableId => Vec<StreamId>;
+
+        /// Tables which votes are cancelled in `on_initialize` before removal
+        pub DestroyQueue get(fn destroy_queue): Vec<T::TableId>;
+
+        /// Accounts allowed to change name and head length of table besides its owner
+        pub TableAdmins get(fn table_admins): map hasher(blake2_256) T::TableId => BTreeSet<AccountId<T>>;
+
+        /// Creator of table, asset and amount of deposit reserved from them
+        pub TableDeposits get(fn table_deposit):
+            map hasher(blake2_256) T::TableId => Option<(AccountId<T>, AssetId<T>, Balance<T>)>;
+
+        /// Count of tables created by account and not destroyed yet
+        pub CreatedTables get(fn created_tables): map hasher(blake2_256) AccountId<T> => u32;
+
+        /// Candidacy deposit of tables in which only registered targets can be voted
+        pub CandidacyDeposits get(fn candidacy_deposit): map hasher(blake2_256) T::TableId => Option<Balance<T>>;
+
+        /// Accounts which can vote in table
+        pub VoterPolicies get(fn voter_policy): map hasher(blake2_256) T::TableId => VoterPolicy;
+
+        /// Accounts that can vote in table with `VoterPolicy::AllowList`
+        pub AllowedVoters get(fn is_allowed_voter):
+            double_map hasher(blake2_256) T::TableId, hasher(blake2_128_concat) AccountId<T> => bool;
+
+        /// Registered target of table with account, asset and amount of its candidacy deposit
+        pub Candidates get(fn candidate):
+            double_map hasher(blake2_256) T::TableId, hasher(blake2_128_concat) T::TargetType
+            => Option<(AccountId<T>, AssetId<T>, Balance<T>)>;
+
+        /// Encoding version of tables in storage
+        pub PalletVersion get(fn pallet_version) build(|_| StorageVersion::latest()): StorageVersion;
+
+        /// Next table to migrate while storage upgrade is in progress
+        pub MigrationCursor get(fn migration_cursor): Option<T::TableId>;
     }
 }
 
@@ -59,17 +250,91 @@ decl_event!(
     where
         AccountId = <T as system::Trait>::AccountId,
         TableId = <T as Trait>::TableId,
-        //TargetType = <T as Trait>::TargetType,
+        TargetType = <T as Trait>::TargetType,
+        Balance = <T as assets::Trait>::Balance,
+        AssetId = <T as assets::Trait>::AssetId,
     {
-        TableCreated(TableId, AccountId),
-        //ChangeVote(TableId, TargetType),
+        /// Table, creator, vote asset, head length, name and reward asset
+        TableCreated(TableId, AccountId, AssetId, u8, Option<Vec<u8>>, Option<AssetId>),
+        /// Table, target, voter, vote-balance added and new total of target
+        Voted(TableId, TargetType, AccountId, Balance, Balance),
+        /// Table, target, voter, vote-balance removed and new total of target
+        Unvoted(TableId, TargetType, AccountId, Balance, Balance),
+        /// Table, target, voter, vote-balance removed and new total of target
+        VoteCancelled(TableId, TargetType, AccountId, Balance, Balance),
+        /// Table, target, sponsor, reward asset and amount
+        RewardAppended(TableId, TargetType, AccountId, AssetId, Balance),
+        /// Table, target, voter, reward asset and amount
+        RewardPaid(TableId, TargetType, AccountId, AssetId, Balance),
+        /// Target lost all votes and left table
+        TargetRemoved(TableId, TargetType),
+        /// Table, targets entered and targets left head of table
+        HeadChanged(TableId, Vec<TargetType>, Vec<TargetType>),
+        /// Table, receiver, asset and amount of swept dust
+        DustSwept(TableId, AccountId, AssetId, Balance),
+        /// Stream, table, sponsor and escrowed amount
+        StreamCreated(StreamId, TableId, AccountId, Balance),
+        /// Stream, sponsor and refunded amount, stream is finished or cancelled
+        StreamClosed(StreamId, AccountId, Balance),
+        /// Table was frozen, unfrozen or started to be destroyed
+        TableStateChanged(TableId, TableState),
+        /// Table with all its votes was removed
+        TableDestroyed(TableId),
+        /// Table, target, voter, reward asset and amount that couldn't be paid
+        /// while destroying table and went to dust
+        RewardToDust(TableId, TargetType, AccountId, AssetId, Balance),
+        /// Table, old and new owner
+        OwnershipTransferred(TableId, AccountId, AccountId),
+        /// Table and its new admins
+        AdminsSet(TableId, Vec<AccountId>),
+        /// Table and its new name
+        TableRenamed(TableId, Option<Vec<u8>>),
+        /// Table and its new head length
+        HeadLenChanged(TableId, u8),
+        /// Table and candidacy deposit, registration of targets is off without it
+        RegistrationSet(TableId, Option<Balance>),
+        /// Table, target and account that paid candidacy deposit
+        TargetRegistered(TableId, TargetType, AccountId),
+        /// Table and target which votes were cancelled
+        TargetDeregistered(TableId, TargetType),
+        /// Table and accounts which can vote in it
+        VoterPolicySet(TableId, VoterPolicy),
+        /// Table and accounts added to its allow-list
+        VotersAllowed(TableId, Vec<AccountId>),
+        /// Table and accounts removed from its allow-list
+        VotersDisallowed(TableId, Vec<AccountId>),
     }
 );
 
 decl_error! {
     pub enum Error for Module<T: Trait> {
         TableIdOverflow,
+        TableNotFound,
+        TargetNotFound,
         VoteNotFound,
+        WalletBalanceLow,
+        TooManyTargets,
+        TooManyVoters,
+        NameTooLong,
+        HeadTooLong,
+        NotOwner,
+        TooManyRewardAssets,
+        EmptyHead,
+        InvalidStreamSchedule,
+        StreamNotFound,
+        TableFrozen,
+        TableDestroying,
+        TooManyAdmins,
+        TooManyTables,
+        DepositBalanceLow,
+        RegistrationDisabled,
+        TargetNotRegistered,
+        TargetAlreadyRegistered,
+        VoterNotAllowed,
+        ZeroVote,
+        RewardOverflow,
+        TooManyStreams,
+        StreamAmountLow,
         NoneValue,
         StorageOverflow,
     }
@@ -82,90 +347,570 @@ decl_module! {
 
         fn deposit_event() = default;
 
+        fn on_runtime_upgrade() -> Weight {
+            migration::migrate::<T>()
+        }
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            let mut weight = migration::migrate_step::<T>();
+
+            // Payment is charged as split over the longest head, the last one
+            // also refunds escrow
+            let payment = T::WeightInfo::append_head_reward(
+                T::MaxTargetsPerTable::get(),
+                T::MaxHeadLen::get() as u32,
+                T::MaxVotersPerTarget::get(),
+                T::MaxRewardAssets::get(),
+            )
+            .saturating_add(T::WeightInfo::cancel_reward_stream());
+            for stream_id in StreamsDue::<T>::take(now) {
+                Self::pay_stream(stream_id, now);
+                weight = weight.saturating_add(payment);
+            }
+
+            let max_votes = T::MaxDestroyVotes::get();
+            let mut budget = max_votes;
+            for table_id in DestroyQueue::<T>::get() {
+                let (rest, removal) = Self::destroy_step(table_id, budget);
+                budget = rest;
+                weight = weight.saturating_add(removal);
+                if budget == 0 {
+                    break;
+                }
+            }
+            // Returned candidacy deposit is charged as cancelled vote
+            let cancel = T::WeightInfo::cancel(
+                T::MaxTargetsPerTable::get(),
+                T::MaxVotersPerTarget::get(),
+                T::MaxRewardAssets::get(),
+            );
+            weight.saturating_add(cancel.saturating_mul(Weight::from(max_votes - budget)))
+        }
+
         /// Creating new table and emit event
-        pub fn create_table(origin, vote_asset: AssetId<T>, head_len: u8, name: Option<Vec<u8>>) -> dispatch::DispatchResult {
-            let who = ensure_signed(origin)?;
-            let id = Self::create(who.clone(), vote_asset, head_len, name)?;
-            Self::deposit_event(Event::<T>::TableCreated(id, who));
+        #[weight = FunctionOf(
+            |args: (&AssetId<T>, &u8, &Option<Vec<u8>>, &Option<AssetId<T>>)| {
+                T::WeightInfo::create_table(args.2.as_ref().map_or(0, |name| name.len() as u32))
+            },
+            DispatchClass::Normal,
+            true
+        )]
+        pub fn create_table(
+            origin,
+            vote_asset: AssetId<T>,
+            head_len: u8,
+            name: Option<Vec<u8>>,
+            reward_asset: Option<AssetId<T>>
+        ) -> dispatch::DispatchResult {
+            let who = T::CreateTableOrigin::ensure_origin(origin)?;
+            let id = Self::create(who.clone(), vote_asset.clone(), head_len, name.clone(), reward_asset.clone())?;
+            Self::deposit_event(Event::<T>::TableCreated(id, who, vote_asset, head_len, name, reward_asset));
 
             Ok(())
         }
 
         /// Vote for the target
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::vote(
+            T::MaxTargetsPerTable::get(),
+            T::MaxVotersPerTarget::get(),
+            T::MaxRewardAssets::get()
+        ))]
         pub fn vote(origin, table_id: T::TableId, vote: Balance<T>, target: T::TargetType) -> dispatch::DispatchResult {
             let who = ensure_signed(origin)?;
-            let table = Scores::<T>::get(table_id);
+            ensure!(Self::can_vote(table_id, &who), Error::<T>::VoterNotAllowed);
+            ensure!(
+                !CandidacyDeposits::<T>::contains_key(table_id) || Candidates::<T>::contains_key(table_id, &target),
+                Error::<T>::TargetNotRegistered
+            );
+            let mut table = Self::load_view(table_id, &target, &who)?;
+            let old_head = Self::owned_head(&table);
+
+            let reward = match table.vote(target.clone(), &who, vote) {
+                VoteResult::Success(reward) => reward,
+                result => Err(Self::vote_error(&result, table.state))?,
+            };
+            Self::ensure_can_pay(&table, &reward)?;
             assets::Module::<T>::reserve(&table.vote_asset, &who, vote)?;
 
-            //Self::deposit_event(Event::<T>::ChangeVote(table_id, target.clone()));
+            let total = Self::target_total(&table, &target);
+            Self::store_view(table_id, &target, &who, &mut table);
+            Self::deposit_event(Event::<T>::Voted(table_id, target.clone(), who.clone(), vote, total));
+            Self::notify_head_change(table_id, old_head, &table);
 
-            match Scores::<T>::mutate(&table_id, |table| table.vote(target, &who, vote)) {
-                VoteResult::Success(Some(reward)) => Self::send_reward(&table.vote_asset, &table.wallet, &who, reward),
-                VoteResult::Success(None) => Ok(()),
-                _ => Err(Error::<T>::NoneValue)?,
-            }
+            Self::pay_reward(table_id, target, &table, &who, reward)
         }
 
         /// Unvote for the target
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::unvote(
+            T::MaxTargetsPerTable::get(),
+            T::MaxVotersPerTarget::get(),
+            T::MaxRewardAssets::get()
+        ))]
         pub fn unvote(origin, table_id: T::TableId, vote: Balance<T>, target: T::TargetType) -> dispatch::DispatchResult {
             let who = ensure_signed(origin)?;
-            let table = Scores::<T>::get(table_id);
+            let mut table = Self::load_view(table_id, &target, &who)?;
+            let old_head = Self::owned_head(&table);
 
-            //Self::deposit_event(Event::<T>::ChangeVote(table_id, target.clone()));
+            let (unvote, reward) = match table.unvote(target.clone(), &who, vote) {
+                VoteResult::Unvoted(unvote, reward) => (unvote, reward),
+                result => Err(Self::vote_error(&result, table.state))?,
+            };
+            Self::ensure_can_pay(&table, &reward)?;
 
-            match Scores::<T>::mutate(&table_id, |table| table.unvote(target, &who, vote)) {
-                VoteResult::Unvoted(unvote, reward) => {
-                    assets::Module::<T>::unreserve(&table.vote_asset, &who, unvote);
-                    if let Some(reward) = reward {
-                        Self::send_reward(&table.vote_asset, &table.wallet, &who, reward)?;
-                    }
-                    Ok(())
-                },
-                VoteResult::VoteNotFound => Err(Error::<T>::VoteNotFound)?,
-                _ => Err(Error::<T>::NoneValue)?,
-            }
+            let total = Self::target_total(&table, &target);
+            Self::store_view(table_id, &target, &who, &mut table);
+            assets::Module::<T>::unreserve(&table.vote_asset, &who, unvote);
+            Self::deposit_event(Event::<T>::Unvoted(table_id, target.clone(), who.clone(), unvote, total));
+            Self::deposit_target_removed(table_id, &target, total);
+            Self::notify_head_change(table_id, old_head, &table);
+
+            Self::pay_reward(table_id, target, &table, &who, reward)
         }
 
         /// Cancel your vote for target
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::cancel(
+            T::MaxTargetsPerTable::get(),
+            T::MaxVotersPerTarget::get(),
+            T::MaxRewardAssets::get()
+        ))]
         pub fn cancel(origin, table_id: T::TableId, target: T::TargetType) -> dispatch::DispatchResult
         {
             let who = ensure_signed(origin)?;
+            Self::cancel_vote(table_id, target, who, false)
+        }
 
-            let table = Scores::<T>::get(table_id);
-            match Scores::<T>::mutate(&table_id, |table| table.cancel(target, &who)) {
-                VoteResult::Unvoted(unvote, reward) => {
-                    assets::Module::<T>::unreserve(&table.vote_asset, &who, unvote);
-                    if let Some(reward) = reward {
-                        Self::send_reward(&table.vote_asset, &table.wallet, &who, reward)?;
-                    }
-                    Ok(())
-                },
-                VoteResult::VoteNotFound => Err(Error::<T>::VoteNotFound)?,
-                _ => Err(Error::<T>::NoneValue)?,
+        /// Store reward for target in `asset` or in reward asset of table
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::append_reward(
+            T::MaxTargetsPerTable::get(),
+            T::MaxVotersPerTarget::get(),
+            T::MaxRewardAssets::get()
+        ))]
+        pub fn append_reward(
+            origin,
+            table_id: T::TableId,
+            balance: Balance<T>,
+            target: T::TargetType,
+            asset: Option<AssetId<T>>
+        ) -> dispatch::DispatchResult {
+            let who = ensure_signed(origin)?;
+            let mut table = Self::load_view(table_id, &target, &who)?;
+            ensure!(table.state != TableState::Destroying, Error::<T>::TableDestroying);
+            let asset = asset.unwrap_or_else(|| table.reward_asset().clone());
+
+            match table.append_reward(target.clone(), asset.clone(), balance) {
+                VoteResult::Success(_) => {}
+                result => Err(Self::vote_error(&result, table.state))?,
             }
+
+            assets::Module::<T>::make_transfer(&asset, &who, &table.wallet, balance)?;
+            assets::Module::<T>::reserve(&asset, &table.wallet, balance)?;
+
+            Self::store_target(table_id, &target, &mut table);
+            Self::deposit_event(Event::<T>::RewardAppended(table_id, target, who, asset, balance));
+
+            Ok(())
         }
 
-        /// Store reward for target
-        pub fn append_reward(origin, table_id: T::TableId, balance: Balance<T>, target: T::TargetType) -> dispatch::DispatchResult {
+        /// Split reward in reward asset of table between targets of table head by `policy`
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::append_head_reward(
+            T::MaxTargetsPerTable::get(),
+            T::MaxHeadLen::get() as u32,
+            T::MaxVotersPerTarget::get(),
+            T::MaxRewardAssets::get()
+        ))]
+        pub fn append_head_reward(
+            origin,
+            table_id: T::TableId,
+            balance: Balance<T>,
+            policy: HeadRewardPolicy
+        ) -> dispatch::DispatchResult {
             let who = ensure_signed(origin)?;
-            let table = Scores::<T>::get(table_id);
+            let mut table = Self::load_head_view(table_id)?;
+            ensure!(table.state != TableState::Destroying, Error::<T>::TableDestroying);
+            let asset = table.reward_asset().clone();
 
-            assets::Module::<T>::make_transfer(&table.vote_asset, &who, &table.wallet, balance)?;
-            assets::Module::<T>::reserve(&table.vote_asset, &table.wallet, balance)?;
+            let shares = Self::append_head_shares(&mut table, &asset, balance, policy)?;
+            let balance = Self::shares_total(&shares);
 
-            Scores::<T>::mutate(&table_id, |table|  table.append_reward(target, balance) ).map_err(|_| Error::<T>::NoneValue)?;
+            assets::Module::<T>::make_transfer(&asset, &who, &table.wallet, balance)?;
+            assets::Module::<T>::reserve(&asset, &table.wallet, balance)?;
+
+            Self::store_summaries(table_id, &mut table);
+            for (target, share) in shares {
+                Self::deposit_event(Event::<T>::RewardAppended(table_id, target, who.clone(), asset.clone(), share));
+            }
+
+            Ok(())
+        }
+
+        /// Escrow `amount` for every `period` blocks until `end` reserved in table wallet
+        /// and pay it to `recipient` at these blocks
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::create_reward_stream())]
+        pub fn create_reward_stream(
+            origin,
+            table_id: T::TableId,
+            recipient: StreamRecipient<T::TargetType>,
+            amount: Balance<T>,
+            period: T::BlockNumber,
+            end: T::BlockNumber
+        ) -> dispatch::DispatchResult {
+            let who = ensure_signed(origin)?;
+            let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+            ensure!(info.state != TableState::Destroying, Error::<T>::TableDestroying);
+
+            ensure!(
+                !amount.is_zero() && amount >= T::MinStreamAmount::get(),
+                Error::<T>::StreamAmountLow
+            );
+            ensure!(
+                (TableStreams::<T>::get(table_id).len() as u32) < T::MaxStreamsPerTable::get(),
+                Error::<T>::TooManyStreams
+            );
+
+            let now = system::Module::<T>::block_number();
+            let first = now
+                .checked_add(&period)
+                .filter(|first| !period.is_zero() && *first <= end)
+                .ok_or(Error::<T>::InvalidStreamSchedule)?;
+            let payments = Balance::<T>::from(UniqueSaturatedInto::<u32>::unique_saturated_into((end - now) / period));
+            let total = amount.checked_mul(&payments).ok_or(Error::<T>::StorageOverflow)?;
+
+            let asset = info.reward_asset().clone();
+            assets::Module::<T>::make_transfer(&asset, &who, &info.wallet, total)?;
+            assets::Module::<T>::reserve(&asset, &info.wallet, total)?;
+
+            let stream_id = StreamIdSequence::mutate(|id| {
+                let result = *id;
+                *id = id.saturating_add(1);
+                result
+            });
+            let next = Self::schedule_stream(stream_id, first);
+            TableStreams::<T>::mutate(table_id, |streams| streams.push(stream_id));
+            RewardStreams::<T>::insert(stream_id, RewardStream::<T> {
+                table_id,
+                recipient,
+                sponsor: who.clone(),
+                asset,
+                amount_per_period: amount,
+                period,
+                next,
+                end,
+                remaining: total,
+            });
+            Self::deposit_event(Event::<T>::StreamCreated(stream_id, table_id, who, total));
+
+            Ok(())
+        }
+
+        /// Stop reward stream and refund the rest of escrow to sponsor, allowed for sponsor
+        /// and `AdminOrigin`
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::cancel_reward_stream())]
+        pub fn cancel_reward_stream(origin, stream_id: StreamId) -> dispatch::DispatchResult {
+            let stream = RewardStreams::<T>::get(stream_id).ok_or(Error::<T>::StreamNotFound)?;
+            Self::ensure_admin_or(origin, &stream.sponsor)?;
+
+            let next = stream.next;
+            Self::close_stream(stream_id, stream)?;
+            StreamsDue::<T>::mutate(next, |due| due.retain(|id| *id != stream_id));
 
             Ok(())
         }
 
         /// Pick up your reward for target
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::pop_reward(
+            T::MaxTargetsPerTable::get(),
+            T::MaxVotersPerTarget::get(),
+            T::MaxRewardAssets::get()
+        ))]
         pub fn pop_reward(origin, table_id: T::TableId, target: T::TargetType) -> dispatch::DispatchResult {
             let who = ensure_signed(origin)?;
-            let table = Scores::<T>::get(table_id);
+            let mut table = Self::load_view(table_id, &target, &who)?;
+
+            let reward = table.pop_reward(&who, target.clone());
+            Self::ensure_can_pay(&table, &reward)?;
+
+            Self::store_view(table_id, &target, &who, &mut table);
+            Self::pay_reward(table_id, target, &table, &who, reward)
+        }
+
+        /// Pick up your rewards for all your targets in tables, one transfer is made per wallet and asset
+        #[weight = FunctionOf(
+            |args: (&Vec<T::TableId>,)| {
+                T::WeightInfo::claim_all(
+                    args.0.len() as u32,
+                    T::MaxTargetsPerTable::get(),
+                    T::MaxVotersPerTarget::get(),
+                    T::MaxRewardAssets::get(),
+                )
+            },
+            DispatchClass::Normal,
+            true
+        )]
+        pub fn claim_all(origin, table_ids: Vec<T::TableId>) -> dispatch::DispatchResult {
+            let who = ensure_signed(origin)?;
+            let table_ids: BTreeSet<T::TableId> = table_ids.into_iter().collect();
+
+            // Tables of the first release share the wallet of their creator
+            let mut payouts: BTreeMap<(AccountId<T>, AssetId<T>), Balance<T>> = BTreeMap::new();
+            let mut views = Vec::new();
+            for table_id in table_ids {
+                for target in VotesByAccount::<T>::get(&who, table_id) {
+                    let mut table = Self::load_view(table_id, &target, &who)?;
+                    if let Some(rewards) = table.pop_reward(&who, target.clone()) {
+                        for (asset, reward) in rewards.iter() {
+                            let sum = payouts.entry((table.wallet.clone(), asset.clone())).or_insert_with(Zero::zero);
+                            *sum = sum.saturating_add(*reward);
+                        }
+                        views.push((table_id, target, table, rewards));
+                    }
+                }
+            }
+
+            for ((wallet, asset), reward) in payouts.iter() {
+                ensure!(
+                    assets::Module::<T>::reserved_balance(asset, wallet) >= *reward,
+                    Error::<T>::WalletBalanceLow
+                );
+            }
+
+            for (table_id, target, mut table, rewards) in views {
+                Self::store_view(table_id, &target, &who, &mut table);
+                for (asset, reward) in rewards {
+                    Self::deposit_event(Event::<T>::RewardPaid(table_id, target.clone(), who.clone(), asset, reward));
+                }
+            }
 
-            if let Some(reward) = Scores::<T>::mutate(&table_id, |table| table.pop_reward(&who, target)) {
-                Self::send_reward(&table.vote_asset, &table.wallet, &who, reward)?;
+            for ((wallet, asset), reward) in payouts {
+                Self::send_reward(&asset, &wallet, &who, reward)?;
             }
+
+            Ok(())
+        }
+
+        /// Stop new votes for table, allowed for table owner and `AdminOrigin`
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_table_state())]
+        pub fn freeze_table(origin, table_id: T::TableId) -> dispatch::DispatchResult {
+            Self::set_state(origin, table_id, TableState::Frozen)
+        }
+
+        /// Take votes for frozen table again, allowed for table owner and `AdminOrigin`
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_table_state())]
+        pub fn unfreeze_table(origin, table_id: T::TableId) -> dispatch::DispatchResult {
+            Self::set_state(origin, table_id, TableState::Active)
+        }
+
+        /// Cancel all votes of table with payout of rewards in next blocks, then send
+        /// dust and the rest of wallet to owner and remove table. Allowed for table owner and `AdminOrigin`
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_table_state())]
+        pub fn destroy_table(origin, table_id: T::TableId) -> dispatch::DispatchResult {
+            Self::set_state(origin, table_id, TableState::Destroying)
+        }
+
+        /// Hand table to `new_owner`, allowed for table owner and `AdminOrigin`
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::transfer_ownership())]
+        pub fn transfer_ownership(origin, table_id: T::TableId, new_owner: AccountId<T>) -> dispatch::DispatchResult {
+            let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+            Self::ensure_table_owner(origin, &info)?;
+
+            let old_owner = info.owner.clone();
+            Tables::<T>::insert(table_id, TableInfo::<T> { owner: new_owner.clone(), ..info });
+            Self::deposit_event(Event::<T>::OwnershipTransferred(table_id, old_owner, new_owner));
+
+            Ok(())
+        }
+
+        /// Replace admins of table, allowed for table owner and `AdminOrigin`
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_admins(T::MaxTableAdmins::get()))]
+        pub fn set_admins(origin, table_id: T::TableId, admins: Vec<AccountId<T>>) -> dispatch::DispatchResult {
+            let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+            Self::ensure_table_owner(origin, &info)?;
+
+            let admins: BTreeSet<AccountId<T>> = admins.into_iter().collect();
+            ensure!(admins.len() <= T::MaxTableAdmins::get() as usize, Error::<T>::TooManyAdmins);
+
+            let event_admins = admins.iter().cloned().collect();
+            if admins.is_empty() {
+                TableAdmins::<T>::remove(table_id);
+            } else {
+                TableAdmins::<T>::insert(table_id, admins);
+            }
+            Self::deposit_event(Event::<T>::AdminsSet(table_id, event_admins));
+
+            Ok(())
+        }
+
+        /// Rename table, allowed for table owner, its admins and `AdminOrigin`
+        #[weight = FunctionOf(
+            |args: (&T::TableId, &Option<Vec<u8>>)| {
+                T::WeightInfo::set_name(args.1.as_ref().map_or(0, |name| name.len() as u32))
+            },
+            DispatchClass::Normal,
+            true
+        )]
+        pub fn set_name(origin, table_id: T::TableId, name: Option<Vec<u8>>) -> dispatch::DispatchResult {
+            let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+            Self::ensure_table_admin(origin, table_id, &info)?;
+            ensure!(
+                name.as_ref().map_or(0, |name| name.len()) <= T::MaxNameLength::get() as usize,
+                Error::<T>::NameTooLong
+            );
+
+            Tables::<T>::insert(table_id, TableInfo::<T> { name: name.clone(), ..info });
+            Self::deposit_event(Event::<T>::TableRenamed(table_id, name));
+
+            Ok(())
+        }
+
+        /// Change count of targets in head of table, allowed for table owner, its admins and `AdminOrigin`
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_head_len(T::MaxTargetsPerTable::get()))]
+        pub fn set_head_len(origin, table_id: T::TableId, head_len: u8) -> dispatch::DispatchResult {
+            let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+            Self::ensure_table_admin(origin, table_id, &info)?;
+            ensure!(info.state != TableState::Destroying, Error::<T>::TableDestroying);
+            ensure!(head_len <= T::MaxHeadLen::get(), Error::<T>::HeadTooLong);
+
+            let mut table = Self::load_table(table_id)?;
+            let old_head = Self::owned_head(&table);
+            table.set_head_count(head_len);
+
+            Tables::<T>::insert(table_id, TableInfo::<T> { head_count: head_len, ..info });
+            Self::deposit_event(Event::<T>::HeadLenChanged(table_id, head_len));
+            Self::notify_head_change(table_id, old_head, &table);
+
+            Ok(())
+        }
+
+        /// Let only targets registered with candidacy `deposit` be voted, `None` lets any
+        /// target be voted. Allowed for table owner and `AdminOrigin`
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_registration())]
+        pub fn set_registration(origin, table_id: T::TableId, deposit: Option<Balance<T>>) -> dispatch::DispatchResult {
+            let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+            Self::ensure_table_owner(origin, &info)?;
+
+            match deposit {
+                Some(deposit) => CandidacyDeposits::<T>::insert(table_id, deposit),
+                None => CandidacyDeposits::<T>::remove(table_id),
+            }
+            Self::deposit_event(Event::<T>::RegistrationSet(table_id, deposit));
+
+            Ok(())
+        }
+
+        /// Register target with candidacy deposit of table, allowed for table owner and
+        /// for account which is the target itself
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::register_target())]
+        pub fn register_target(origin, table_id: T::TableId, target: T::TargetType) -> dispatch::DispatchResult {
+            let who = ensure_signed(origin)?;
+            let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+            ensure!(info.state != TableState::Destroying, Error::<T>::TableDestroying);
+            let deposit = CandidacyDeposits::<T>::get(table_id).ok_or(Error::<T>::RegistrationDisabled)?;
+            ensure!(
+                who == info.owner || T::AccountTarget::convert(who.clone()).as_ref() == Some(&target),
+                Error::<T>::NotOwner
+            );
+            ensure!(!Candidates::<T>::contains_key(table_id, &target), Error::<T>::TargetAlreadyRegistered);
+
+            let asset = T::DepositAsset::get();
+            assets::Module::<T>::reserve(&asset, &who, deposit).map_err(|_| Error::<T>::DepositBalanceLow)?;
+            Candidates::<T>::insert(table_id, &target, (who.clone(), asset, deposit));
+            Self::deposit_event(Event::<T>::TargetRegistered(table_id, target, who));
+
+            Ok(())
+        }
+
+        /// Cancel all votes for target with payout of rewards and return candidacy deposit,
+        /// allowed for account that registered target, table owner and `AdminOrigin`.
+        /// Rewards that can't be paid go to dust of table
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::deregister_target(
+            T::MaxTargetsPerTable::get(),
+            T::MaxVotersPerTarget::get()
+        ))]
+        pub fn deregister_target(origin, table_id: T::TableId, target: T::TargetType) -> dispatch::DispatchResult {
+            let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+            let (depositor, asset, deposit) =
+                Candidates::<T>::get(table_id, &target).ok_or(Error::<T>::TargetNotRegistered)?;
+            if let Err(origin) = T::AdminOrigin::try_origin(origin) {
+                let who = ensure_signed(origin)?;
+                ensure!(who == depositor || who == info.owner, Error::<T>::NotOwner);
+            }
+
+            // Forced cancel doesn't fail on reward that can't be paid, so
+            // deregistration doesn't stop with a part of votes cancelled
+            for voter in Self::voters_of(table_id, &target) {
+                Self::cancel_vote(table_id, target.clone(), voter, true)?;
+            }
+            Candidates::<T>::remove(table_id, &target);
+            assets::Module::<T>::unreserve(&asset, &depositor, deposit);
+            Self::deposit_event(Event::<T>::TargetDeregistered(table_id, target));
+
+            Ok(())
+        }
+
+        /// Choose accounts which can vote in table, allowed for table owner and `AdminOrigin`.
+        /// Votes given before stay until voters take them back
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_voter_policy())]
+        pub fn set_voter_policy(origin, table_id: T::TableId, policy: VoterPolicy) -> dispatch::DispatchResult {
+            let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+            Self::ensure_table_owner(origin, &info)?;
+
+            VoterPolicies::<T>::insert(table_id, policy);
+            Self::deposit_event(Event::<T>::VoterPolicySet(table_id, policy));
+
+            Ok(())
+        }
+
+        /// Add accounts to allow-list of table, allowed for table owner and `AdminOrigin`
+        #[weight = FunctionOf(
+            |args: (&T::TableId, &Vec<AccountId<T>>)| T::WeightInfo::allow_voters(args.1.len() as u32),
+            DispatchClass::Normal,
+            true
+        )]
+        pub fn allow_voters(origin, table_id: T::TableId, voters: Vec<AccountId<T>>) -> dispatch::DispatchResult {
+            let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+            Self::ensure_table_owner(origin, &info)?;
+
+            for voter in voters.iter() {
+                AllowedVoters::<T>::insert(table_id, voter, true);
+            }
+            Self::deposit_event(Event::<T>::VotersAllowed(table_id, voters));
+
+            Ok(())
+        }
+
+        /// Remove accounts from allow-list of table, allowed for table owner and `AdminOrigin`
+        #[weight = FunctionOf(
+            |args: (&T::TableId, &Vec<AccountId<T>>)| T::WeightInfo::disallow_voters(args.1.len() as u32),
+            DispatchClass::Normal,
+            true
+        )]
+        pub fn disallow_voters(origin, table_id: T::TableId, voters: Vec<AccountId<T>>) -> dispatch::DispatchResult {
+            let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+            Self::ensure_table_owner(origin, &info)?;
+
+            for voter in voters.iter() {
+                AllowedVoters::<T>::remove(table_id, voter);
+            }
+            Self::deposit_event(Event::<T>::VotersDisallowed(table_id, voters));
+
+            Ok(())
+        }
+
+        /// Send undistributed reward dust of table in `asset` to `dest`, allowed for table owner and `AdminOrigin`
+        #[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::sweep_dust())]
+        pub fn sweep_dust(origin, table_id: T::TableId, asset: AssetId<T>, dest: AccountId<T>) -> dispatch::DispatchResult {
+            let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+            Self::ensure_table_owner(origin, &info)?;
+
+            let dust = TableDust::<T>::get(table_id, &asset);
+            ensure!(
+                assets::Module::<T>::reserved_balance(&asset, &info.wallet) >= dust,
+                Error::<T>::WalletBalanceLow
+            );
+
+            TableDust::<T>::remove(table_id, &asset);
+            Self::send_reward(&asset, &info.wallet, &dest, dust)?;
+            Self::deposit_event(Event::<T>::DustSwept(table_id, dest, asset, dust));
+
             Ok(())
         }
     }
@@ -177,12 +922,557 @@ impl<T: Trait> Module<T> {
         vote_asset: AssetId<T>,
         head_len: u8,
         name: Option<Vec<u8>>,
+        reward_asset: Option<AssetId<T>>,
     ) -> Result<T::TableId, Error<T>> {
-        let id = Self::get_next_table_id()?;
-        Scores::<T>::insert(id, Table::<T>::new(name, head_len, vote_asset, who)); // ToDo create normal wallet
+        ensure!(head_len <= T::MaxHeadLen::get(), Error::<T>::HeadTooLong);
+        ensure!(
+            name.as_ref().map_or(0, |name| name.len()) <= T::MaxNameLength::get() as usize,
+            Error::<T>::NameTooLong
+        );
+        ensure!(
+            CreatedTables::<T>::get(&who) < T::MaxTablesPerAccount::get(),
+            Error::<T>::TooManyTables
+        );
+
+        let deposit_asset = T::DepositAsset::get();
+        let deposit = T::TableDeposit::get();
+        assets::Module::<T>::reserve(&deposit_asset, &who, deposit).map_err(|_| Error::<T>::DepositBalanceLow)?;
+        let id = Self::get_next_table_id().map_err(|err| {
+            assets::Module::<T>::unreserve(&deposit_asset, &who, deposit);
+            err
+        })?;
+
+        TableDeposits::<T>::insert(id, (who.clone(), deposit_asset, deposit));
+        CreatedTables::<T>::mutate(&who, |count| *count += 1);
+        Tables::<T>::insert(
+            id,
+            TableInfo::<T> {
+                name,
+                head_count: head_len,
+                vote_asset,
+                reward_asset,
+                wallet: Self::table_wallet(id),
+                owner: who,
+                state: TableState::Active,
+            },
+        );
         Ok(id)
     }
 
+    /// Keyless account that keeps reward tokens of table
+    pub fn table_wallet(table_id: T::TableId) -> AccountId<T> {
+        T::ModuleId::get().into_sub_account(table_id)
+    }
+
+    /// Wallet of existing table
+    pub fn wallet(table_id: T::TableId) -> Option<AccountId<T>> {
+        Tables::<T>::get(table_id).map(|info| info.wallet)
+    }
+
+    /// Targets with the biggest vote-balance in table
+    pub fn head(table_id: T::TableId) -> Vec<T::TargetType> {
+        let head_count = Tables::<T>::get(table_id).map_or(0, |info| info.head_count);
+        TableScores::<T>::get(table_id)
+            .iter()
+            .take(head_count as usize)
+            .map(|record| record.get_target().clone())
+            .collect()
+    }
+
+    /// Total vote-balance of target
+    pub fn score_of(table_id: T::TableId, target: T::TargetType) -> Option<Balance<T>> {
+        Targets::<T>::get(table_id, target).map(|summary| summary.total)
+    }
+
+    /// Place of target in table, the leader has rank 1
+    pub fn rank_of(table_id: T::TableId, target: T::TargetType) -> Option<u32> {
+        TableScores::<T>::get(table_id)
+            .iter()
+            .position(|record| *record.get_target() == target)
+            .map(|position| position as u32 + 1)
+    }
+
+    /// Vote-balance of voter for target
+    pub fn vote_of(table_id: T::TableId, target: T::TargetType, voter: AccountId<T>) -> Option<Balance<T>> {
+        Votes::<T>::get((table_id, target), voter)
+    }
+
+    /// Vote-balances of account for every target it voted for in every table
+    pub fn votes_of_account(voter: AccountId<T>) -> Vec<(T::TableId, T::TargetType, Balance<T>)> {
+        let mut votes = Vec::new();
+        for table_id in double_map_keys::<VotesByAccount<T>, _, _, _>(&voter) {
+            for target in VotesByAccount::<T>::get(&voter, table_id) {
+                if let Some(vote) = Votes::<T>::get((table_id, target.clone()), &voter) {
+                    votes.push((table_id, target, vote));
+                }
+            }
+        }
+        votes
+    }
+
+    /// Voters with vote for table target
+    fn voters_of(table_id: T::TableId, target: &T::TargetType) -> Vec<AccountId<T>> {
+        double_map_keys::<Votes<T>, _, _, _>(&(table_id, target.clone()))
+    }
+
+    /// Rewards by asset that voter can pick up for target, storage stays untouched
+    pub fn pending_reward(
+        table_id: T::TableId,
+        target: T::TargetType,
+        voter: AccountId<T>,
+    ) -> Vec<(AssetId<T>, Balance<T>)> {
+        Self::load_view(table_id, &target, &voter)
+            .ok()
+            .and_then(|mut table| table.pop_reward(&voter, target))
+            .map_or_else(Vec::new, |rewards| rewards.into_iter().collect())
+    }
+
+    /// Load table with scores and the state of the single target and voter.
+    ///
+    /// Only storage entries touched by an operation of the voter are decoded,
+    /// so the cost doesn't depend on count of other voters of the target.
+    fn load_view(
+        table_id: T::TableId,
+        target: &T::TargetType,
+        voter: &AccountId<T>,
+    ) -> Result<Table<T>, Error<T>> {
+        let mut table = Self::load_table(table_id)?;
+
+        if let Some(summary) = Targets::<T>::get(table_id, target) {
+            let key = (table_id, target.clone());
+            let votes = Votes::<T>::get(&key, voter)
+                .map(|vote| (voter.clone(), vote))
+                .into_iter()
+                .collect();
+            let origin = Checkpoints::<T>::get(&key, voter)
+                .map(|period| (voter.clone(), period))
+                .into_iter()
+                .collect();
+
+            table
+                .targets
+                .insert(target.clone(), TargetData::<T>::from_parts(summary, votes, origin));
+        }
+
+        Ok(table)
+    }
+
+    /// Load table with scores and summaries of targets in its head, without voters
+    fn load_head_view(table_id: T::TableId) -> Result<Table<T>, Error<T>> {
+        let mut table = Self::load_table(table_id)?;
+
+        for target in Self::owned_head(&table) {
+            if let Some(summary) = Targets::<T>::get(table_id, &target) {
+                table.targets.insert(
+                    target,
+                    TargetData::<T>::from_parts(summary, BTreeMap::new(), BTreeMap::new()),
+                );
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// Load table settings and scores bounded by limits of the pallet
+    fn load_table(table_id: T::TableId) -> Result<Table<T>, Error<T>> {
+        let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+        Ok(Table::<T>::from_info(info, TableScores::<T>::get(table_id)).with_limits(Limits {
+            max_targets: T::MaxTargetsPerTable::get(),
+            max_voters: T::MaxVotersPerTarget::get(),
+            max_reward_assets: T::MaxRewardAssets::get(),
+        }))
+    }
+
+    /// Write back scores, target and voter state changed in view
+    fn store_view(table_id: T::TableId, target: &T::TargetType, voter: &AccountId<T>, table: &mut Table<T>) {
+        let key = (table_id, target.clone());
+        match Self::store_target(table_id, target, table) {
+            Some((votes, origin)) => {
+                match votes.get(voter) {
+                    Some(vote) => Votes::<T>::insert(&key, voter, vote),
+                    None => Votes::<T>::remove(&key, voter),
+                }
+                Self::index_vote(table_id, target, voter, votes.contains_key(voter));
+                match origin.get(voter) {
+                    Some(period) => Checkpoints::<T>::insert(&key, voter, period),
+                    None => Checkpoints::<T>::remove(&key, voter),
+                }
+            }
+            None => {
+                Votes::<T>::remove(&key, voter);
+                Checkpoints::<T>::remove(&key, voter);
+                Self::index_vote(table_id, target, voter, false);
+            }
+        }
+    }
+
+    /// Keep target in `VotesByAccount` of voter while they have vote for it
+    fn index_vote(table_id: T::TableId, target: &T::TargetType, voter: &AccountId<T>, has_vote: bool) {
+        let mut targets = VotesByAccount::<T>::get(voter, table_id);
+        let changed = if has_vote {
+            targets.insert(target.clone())
+        } else {
+            targets.remove(target)
+        };
+
+        if !changed {
+            return;
+        }
+        if targets.is_empty() {
+            VotesByAccount::<T>::remove(voter, table_id);
+        } else {
+            VotesByAccount::<T>::insert(voter, table_id, targets);
+        }
+    }
+
+    /// Write back scores, target summary and dust, return the state of loaded voters
+    fn store_target(
+        table_id: T::TableId,
+        target: &T::TargetType,
+        table: &mut Table<T>,
+    ) -> Option<(
+        BTreeMap<AccountId<T>, Balance<T>>,
+        BTreeMap<AccountId<T>, T::PeriodType>,
+    )> {
+        TableScores::<T>::insert(table_id, &table.scores);
+
+        for (asset, amount) in rstd::mem::replace(&mut table.dust, BTreeMap::new()) {
+            TableDust::<T>::mutate(table_id, asset, |dust| *dust = dust.saturating_add(amount));
+        }
+
+        match table.targets.remove(target) {
+            Some(data) => {
+                let (summary, votes, origin) = data.into_parts();
+                Targets::<T>::insert(table_id, target, summary);
+                Some((votes, origin))
+            }
+            None => {
+                Targets::<T>::remove(table_id, target);
+                None
+            }
+        }
+    }
+
+    /// Cancel vote of voter for target, unreserve it and pay out reward
+    /// Cancel vote and pay its reward.
+    ///
+    /// With `force` the vote is cancelled even if its reward can't be paid,
+    /// then the reward goes to dust of table.
+    fn cancel_vote(
+        table_id: T::TableId,
+        target: T::TargetType,
+        who: AccountId<T>,
+        force: bool,
+    ) -> dispatch::DispatchResult {
+        let mut table = Self::load_view(table_id, &target, &who)?;
+        let old_head = Self::owned_head(&table);
+
+        let (unvote, reward) = match table.cancel(target.clone(), &who) {
+            VoteResult::Unvoted(unvote, reward) => (unvote, reward),
+            result => Err(Self::vote_error(&result, table.state))?,
+        };
+        let can_pay = Self::ensure_can_pay(&table, &reward);
+        if !force {
+            can_pay?;
+        }
+
+        let total = Self::target_total(&table, &target);
+        Self::store_view(table_id, &target, &who, &mut table);
+        assets::Module::<T>::unreserve(&table.vote_asset, &who, unvote);
+        Self::deposit_event(Event::<T>::VoteCancelled(table_id, target.clone(), who.clone(), unvote, total));
+        Self::deposit_target_removed(table_id, &target, total);
+        Self::notify_head_change(table_id, old_head, &table);
+
+        if can_pay.is_ok() {
+            return Self::pay_reward(table_id, target, &table, &who, reward);
+        }
+        for (asset, reward) in reward.into_iter().flatten() {
+            TableDust::<T>::mutate(table_id, &asset, |dust| *dust = dust.saturating_add(reward));
+            Self::deposit_event(Event::<T>::RewardToDust(table_id, target.clone(), who.clone(), asset, reward));
+        }
+        Ok(())
+    }
+
+    /// Set state of table that isn't being destroyed
+    fn set_state(origin: T::Origin, table_id: T::TableId, state: TableState) -> dispatch::DispatchResult {
+        let info = Tables::<T>::get(table_id).ok_or(Error::<T>::TableNotFound)?;
+        Self::ensure_table_owner(origin, &info)?;
+        ensure!(info.state != TableState::Destroying, Error::<T>::TableDestroying);
+
+        Tables::<T>::insert(table_id, TableInfo::<T> { state, ..info });
+        if state == TableState::Destroying {
+            DestroyQueue::<T>::mutate(|queue| queue.push(table_id));
+        }
+        Self::deposit_event(Event::<T>::TableStateChanged(table_id, state));
+
+        Ok(())
+    }
+
+    /// Cancel up to `budget` votes of destroying table, then return candidacy
+    /// deposits out of the rest of budget and remove the table when neither
+    /// is left, return the rest of budget and weight of removal.
+    ///
+    /// Votes which reward can't be paid are cancelled anyway, so a table
+    /// can't be stuck in the queue.
+    fn destroy_step(table_id: T::TableId, mut budget: u32) -> (u32, Weight) {
+        for record in TableScores::<T>::get(table_id) {
+            let target = record.get_target();
+            for voter in Self::voters_of(table_id, target) {
+                if budget == 0 {
+                    return (budget, 0);
+                }
+                budget -= 1;
+                let _ = Self::cancel_vote(table_id, target.clone(), voter, true);
+            }
+        }
+
+        if !TableScores::<T>::get(table_id).is_empty() {
+            return (budget, 0);
+        }
+
+        // Candidacy deposits are returned out of the same budget as votes
+        for target in double_map_first_keys::<Candidates<T>, _, _, _>(&table_id, budget as usize) {
+            budget -= 1;
+            if let Some((depositor, asset, deposit)) = Candidates::<T>::take(table_id, &target) {
+                assets::Module::<T>::unreserve(&asset, &depositor, deposit);
+            }
+        }
+        if !double_map_first_keys::<Candidates<T>, _, _, _>(&table_id, 1).is_empty() {
+            return (budget, 0);
+        }
+        (budget, Self::remove_table(table_id))
+    }
+
+    /// Close streams of table, send dust and the rest of wallet to owner, return
+    /// deposit to creator and remove table. Candidacy deposits are returned by
+    /// `destroy_step` before
+    fn remove_table(table_id: T::TableId) -> Weight {
+        let info = match Tables::<T>::get(table_id) {
+            Some(info) => info,
+            None => {
+                DestroyQueue::<T>::mutate(|queue| queue.retain(|id| *id != table_id));
+                return T::WeightInfo::remove_table(0);
+            }
+        };
+
+        let streams = TableStreams::<T>::get(table_id);
+        for stream_id in streams.iter().cloned() {
+            if let Some(stream) = RewardStreams::<T>::get(stream_id) {
+                let next = stream.next;
+                if Self::close_stream(stream_id, stream).is_ok() {
+                    StreamsDue::<T>::mutate(next, |due| due.retain(|id| *id != stream_id));
+                }
+            }
+        }
+
+        let mut assets: BTreeSet<AssetId<T>> = double_map_keys::<TableDust<T>, _, _, _>(&table_id).into_iter().collect();
+        for asset in assets.iter() {
+            let _ = Self::send_reward(asset, &info.wallet, &info.owner, TableDust::<T>::get(table_id, asset));
+        }
+
+        // Wallet of tables from the first release is the account of their
+        // creator, its other tokens aren't leftovers of table
+        if info.wallet == Self::table_wallet(table_id) {
+            assets.insert(info.vote_asset.clone());
+            assets.insert(info.reward_asset().clone());
+            for asset in assets.iter() {
+                let reserved = assets::Module::<T>::reserved_balance(asset, &info.wallet);
+                let _ = Self::send_reward(asset, &info.wallet, &info.owner, reserved);
+                let free = assets::Module::<T>::free_balance(asset, &info.wallet);
+                let _ = assets::Module::<T>::make_transfer(asset, &info.wallet, &info.owner, free);
+            }
+        }
+
+        Tables::<T>::remove(table_id);
+        TableScores::<T>::remove(table_id);
+        TableDust::<T>::remove_prefix(table_id);
+        TableStreams::<T>::remove(table_id);
+        TableAdmins::<T>::remove(table_id);
+        CandidacyDeposits::<T>::remove(table_id);
+        VoterPolicies::<T>::remove(table_id);
+        AllowedVoters::<T>::remove_prefix(table_id);
+        if let Some((creator, asset, deposit)) = TableDeposits::<T>::take(table_id) {
+            assets::Module::<T>::unreserve(&asset, &creator, deposit);
+            CreatedTables::<T>::mutate(&creator, |count| *count = count.saturating_sub(1));
+        }
+        DestroyQueue::<T>::mutate(|queue| queue.retain(|id| *id != table_id));
+        Self::deposit_event(Event::<T>::TableDestroyed(table_id));
+
+        T::WeightInfo::remove_table(streams.len() as u32)
+    }
+
+    /// Append shares of `balance` to targets of head in view and return the shares
+    /// taken. A target that can't take its share is skipped, fail only when no
+    /// target of head takes a share
+    fn append_head_shares(
+        table: &mut Table<T>,
+        asset: &AssetId<T>,
+        balance: Balance<T>,
+        policy: HeadRewardPolicy,
+    ) -> Result<Vec<(T::TargetType, Balance<T>)>, Error<T>> {
+        let shares = table.head_shares(balance, policy);
+        ensure!(!shares.is_empty(), Error::<T>::EmptyHead);
+        let mut error = Error::<T>::EmptyHead;
+        let mut taken = Vec::with_capacity(shares.len());
+        for (target, share) in shares {
+            match table.append_reward(target.clone(), asset.clone(), share) {
+                VoteResult::Success(_) => taken.push((target, share)),
+                result => error = Self::vote_error(&result, table.state),
+            }
+        }
+        ensure!(!taken.is_empty(), error);
+        Ok(taken)
+    }
+
+    /// Put stream to the first block from `at` with room for payments
+    fn schedule_stream(stream_id: StreamId, mut at: T::BlockNumber) -> T::BlockNumber {
+        while StreamsDue::<T>::get(at).len() >= T::MaxStreamsPerBlock::get() as usize {
+            at += One::one();
+        }
+        StreamsDue::<T>::mutate(at, |due| due.push(stream_id));
+        at
+    }
+
+    /// Pay one period of stream and schedule the next one or close the stream.
+    ///
+    /// Payment that can't be appended, e.g. to target without votes or to empty
+    /// head, is skipped and stays in escrow, as well as shares of head targets
+    /// that can't take them.
+    fn pay_stream(stream_id: StreamId, now: T::BlockNumber) {
+        let mut stream = match RewardStreams::<T>::get(stream_id) {
+            Some(stream) => stream,
+            None => return,
+        };
+
+        let amount = stream.amount_per_period.min(stream.remaining);
+        if let Ok(credited) = Self::credit_stream(&stream, amount) {
+            stream.remaining = stream.remaining.saturating_sub(credited);
+        }
+
+        match now.checked_add(&stream.period) {
+            Some(next) if next <= stream.end && !stream.remaining.is_zero() => {
+                stream.next = Self::schedule_stream(stream_id, next);
+                RewardStreams::<T>::insert(stream_id, stream);
+            }
+            _ => {
+                let _ = Self::close_stream(stream_id, stream);
+            }
+        }
+    }
+
+    /// Append `amount` of stream escrow to its recipient, it stays reserved in table
+    /// wallet as reward. Returns the part of `amount` that was appended
+    fn credit_stream(stream: &RewardStream<T>, amount: Balance<T>) -> Result<Balance<T>, dispatch::DispatchError> {
+        let table_id = stream.table_id;
+        ensure!(
+            Tables::<T>::get(table_id).map_or(false, |info| info.state != TableState::Destroying),
+            Error::<T>::TableDestroying
+        );
+        let shares = match &stream.recipient {
+            StreamRecipient::Target(target) => {
+                let mut table = Self::load_table(table_id)?;
+                let summary = Targets::<T>::get(table_id, target).ok_or(Error::<T>::TargetNotFound)?;
+                table.targets.insert(
+                    target.clone(),
+                    TargetData::<T>::from_parts(summary, BTreeMap::new(), BTreeMap::new()),
+                );
+                match table.append_reward(target.clone(), stream.asset.clone(), amount) {
+                    VoteResult::Success(_) => {}
+                    result => Err(Self::vote_error(&result, table.state))?,
+                }
+                Self::store_summaries(table_id, &mut table);
+                Some((target.clone(), amount)).into_iter().collect()
+            }
+            StreamRecipient::Head(policy) => {
+                let mut table = Self::load_head_view(table_id)?;
+                let shares = Self::append_head_shares(&mut table, &stream.asset, amount, *policy)?;
+                Self::store_summaries(table_id, &mut table);
+                shares
+            }
+        };
+
+        let credited = Self::shares_total(&shares);
+        for (target, share) in shares {
+            Self::deposit_event(Event::<T>::RewardAppended(
+                table_id,
+                target,
+                stream.sponsor.clone(),
+                stream.asset.clone(),
+                share,
+            ));
+        }
+        Ok(credited)
+    }
+
+    /// Sum of shares appended to targets
+    fn shares_total(shares: &[(T::TargetType, Balance<T>)]) -> Balance<T> {
+        shares
+            .iter()
+            .fold(Zero::zero(), |total: Balance<T>, (_, share)| total.saturating_add(*share))
+    }
+
+    /// Remove stream and refund the rest of its escrow to sponsor
+    fn close_stream(stream_id: StreamId, stream: RewardStream<T>) -> dispatch::DispatchResult {
+        if !stream.remaining.is_zero() {
+            let wallet = Self::wallet(stream.table_id).ok_or(Error::<T>::TableNotFound)?;
+            Self::send_reward(&stream.asset, &wallet, &stream.sponsor, stream.remaining)?;
+        }
+
+        RewardStreams::<T>::remove(stream_id);
+        TableStreams::<T>::mutate(stream.table_id, |streams| streams.retain(|id| *id != stream_id));
+        Self::deposit_event(Event::<T>::StreamClosed(stream_id, stream.sponsor, stream.remaining));
+        Ok(())
+    }
+
+    /// Write back summaries of loaded targets, scores and voters stay untouched
+    fn store_summaries(table_id: T::TableId, table: &mut Table<T>) {
+        for (target, data) in rstd::mem::replace(&mut table.targets, BTreeMap::new()) {
+            let (summary, _votes, _origin) = data.into_parts();
+            Targets::<T>::insert(table_id, target, summary);
+        }
+    }
+
+    /// Check voter policy of table for account
+    pub fn can_vote(table_id: T::TableId, who: &AccountId<T>) -> bool {
+        match VoterPolicies::<T>::get(table_id) {
+            VoterPolicy::Open => true,
+            VoterPolicy::AllowList => AllowedVoters::<T>::get(table_id, who),
+            VoterPolicy::Members => T::VoterFilter::contains(who),
+        }
+    }
+
+    /// Pass `AdminOrigin` and owner of table
+    fn ensure_table_owner(origin: T::Origin, info: &TableInfo<T>) -> dispatch::DispatchResult {
+        Self::ensure_admin_or(origin, &info.owner)
+    }
+
+    /// Pass `AdminOrigin` and `account`
+    fn ensure_admin_or(origin: T::Origin, account: &AccountId<T>) -> dispatch::DispatchResult {
+        match T::AdminOrigin::try_origin(origin) {
+            Ok(_) => Ok(()),
+            Err(origin) => {
+                let who = ensure_signed(origin)?;
+                ensure!(who == *account, Error::<T>::NotOwner);
+                Ok(())
+            }
+        }
+    }
+
+    /// Pass `AdminOrigin`, owner and admins of table
+    fn ensure_table_admin(origin: T::Origin, table_id: T::TableId, info: &TableInfo<T>) -> dispatch::DispatchResult {
+        match T::AdminOrigin::try_origin(origin) {
+            Ok(_) => Ok(()),
+            Err(origin) => {
+                let who = ensure_signed(origin)?;
+                ensure!(
+                    who == info.owner || TableAdmins::<T>::get(table_id).contains(&who),
+                    Error::<T>::NotOwner
+                );
+                Ok(())
+            }
+        }
+    }
+
     fn get_next_table_id() -> Result<T::TableId, Error<T>> {
         TableIdSequence::<T>::mutate(|id| match id.checked_add(&One::one()) {
             Some(res) => {
@@ -194,17 +1484,147 @@ impl<T: Trait> Module<T> {
         })
     }
 
+    /// Error of table operation that didn't succeed in `state` of table.
+    ///
+    /// Success of another operation, like `Unvoted` of vote, means that
+    /// the vote wasn't found.
+    fn vote_error<V, R>(result: &VoteResult<V, R>, state: TableState) -> Error<T> {
+        match result {
+            VoteResult::TargetNotFound => Error::<T>::TargetNotFound,
+            VoteResult::TargetsLimit => Error::<T>::TooManyTargets,
+            VoteResult::VotersLimit => Error::<T>::TooManyVoters,
+            VoteResult::RewardAssetsLimit => Error::<T>::TooManyRewardAssets,
+            VoteResult::TableFrozen if state == TableState::Destroying => Error::<T>::TableDestroying,
+            VoteResult::TableFrozen => Error::<T>::TableFrozen,
+            VoteResult::ZeroVote => Error::<T>::ZeroVote,
+            VoteResult::RewardOverflow => Error::<T>::RewardOverflow,
+            VoteResult::Success(_) | VoteResult::Unvoted(..) | VoteResult::VoteNotFound => Error::<T>::VoteNotFound,
+        }
+    }
+
+    /// Check that table wallet keeps enough reserved tokens for reward.
+    ///
+    /// Dispatchables do all fallible work before the first write: table changes
+    /// are calculated in memory, then the payout is checked and voter tokens are
+    /// reserved, and only after that the view is stored and reward is sent.
+    fn ensure_can_pay(table: &Table<T>, rewards: &Option<Rewards<T>>) -> dispatch::DispatchResult {
+        for (asset, reward) in rewards.iter().flatten() {
+            ensure!(
+                assets::Module::<T>::reserved_balance(asset, &table.wallet) >= *reward,
+                Error::<T>::WalletBalanceLow
+            );
+        }
+        Ok(())
+    }
+
+    fn pay_reward(
+        table_id: T::TableId,
+        target: T::TargetType,
+        table: &Table<T>,
+        who: &AccountId<T>,
+        rewards: Option<Rewards<T>>,
+    ) -> dispatch::DispatchResult {
+        for (asset, reward) in rewards.into_iter().flatten() {
+            Self::send_reward(&asset, &table.wallet, who, reward)?;
+            Self::deposit_event(Event::<T>::RewardPaid(table_id, target.clone(), who.clone(), asset, reward));
+        }
+        Ok(())
+    }
+
+    fn target_total(table: &Table<T>, target: &T::TargetType) -> Balance<T> {
+        table
+            .targets
+            .get(target)
+            .map_or_else(Zero::zero, |data| data.total)
+    }
+
+    fn owned_head(table: &Table<T>) -> Vec<T::TargetType> {
+        table.get_head().into_iter().cloned().collect()
+    }
+
+    /// Compare head of table with head before operation and report the difference
+    fn notify_head_change(table_id: T::TableId, old_head: Vec<T::TargetType>, table: &Table<T>) {
+        let new_head = Self::owned_head(table);
+        let entered: Vec<T::TargetType> = new_head
+            .iter()
+            .filter(|target| !old_head.contains(target))
+            .cloned()
+            .collect();
+        let left: Vec<T::TargetType> = old_head
+            .into_iter()
+            .filter(|target| !new_head.contains(target))
+            .collect();
+
+        if !entered.is_empty() || !left.is_empty() {
+            T::OnHeadChange::on_head_change(table_id, &entered, &left);
+            Self::deposit_event(Event::<T>::HeadChanged(table_id, entered, left));
+        }
+    }
+
+    fn deposit_target_removed(table_id: T::TableId, target: &T::TargetType, total: Balance<T>) {
+        if total.is_zero() {
+            Self::deposit_event(Event::<T>::TargetRemoved(table_id, target.clone()));
+        }
+    }
+
+    /// Move `balance` from reserved balance of wallet to `who`, fail without
+    /// changes if the wallet has less reserved
     fn send_reward(
         asset_id: &AssetId<T>,
         wallet: &T::AccountId,
         who: &T::AccountId,
         balance: Balance<T>,
     ) -> dispatch::DispatchResult {
-        assets::Module::<T>::make_transfer(
-            asset_id,
-            wallet,
-            who,
-            assets::Module::<T>::unreserve(asset_id, wallet, balance),
-        )
+        ensure!(
+            assets::Module::<T>::reserved_balance(asset_id, wallet) >= balance,
+            Error::<T>::WalletBalanceLow
+        );
+        // Returns the part that couldn't be unreserved, none is left after the check
+        let rest = assets::Module::<T>::unreserve(asset_id, wallet, balance);
+        assets::Module::<T>::make_transfer(asset_id, wallet, who, balance.saturating_sub(rest))
+    }
+}
+
+/// Second keys of pallet double map `M` under the first key `key1`.
+///
+/// Keys are read from raw storage: the first key of the map must be hashed
+/// with `blake2_256` and the second one with `blake2_128_concat`, so its
+/// encoding follows its 16-byte hash.
+fn double_map_keys<M, K1, K2, V>(key1: &K1) -> Vec<K2>
+where
+    M: generator::StorageDoubleMap<K1, K2, V>,
+    K1: FullEncode,
+    K2: FullCodec,
+    V: FullCodec,
+{
+    double_map_first_keys::<M, K1, K2, V>(key1, usize::max_value())
+}
+
+/// At most `limit` first keys of `double_map_keys`, the rest isn't read
+fn double_map_first_keys<M, K1, K2, V>(key1: &K1, limit: usize) -> Vec<K2>
+where
+    M: generator::StorageDoubleMap<K1, K2, V>,
+    K1: FullEncode,
+    K2: FullCodec,
+    V: FullCodec,
+{
+    let mut prefix = twox_128(M::module_prefix()).to_vec();
+    prefix.extend_from_slice(&twox_128(M::storage_prefix()));
+    prefix.extend_from_slice(&blake2_256(&key1.encode()));
+
+    let mut keys = Vec::new();
+    let mut key = prefix.clone();
+    while keys.len() < limit {
+        let next = match sp_io::storage::next_key(&key) {
+            Some(next) if next.starts_with(&prefix) => next,
+            _ => break,
+        };
+        if let Some(mut encoded) = next.get(prefix.len() + 16..) {
+            if let Ok(key2) = K2::decode(&mut encoded) {
+                keys.push(key2);
+            }
+        }
+        key = next;
     }
+    keys
 }
@@ -0,0 +1,367 @@
+//! Storage migrations between encodings of tables
+
+use codec::{Decode, Encode};
+use frame_support::{
+    storage::{generator::StorageMap as _, unhashed, StorageDoubleMap, StorageMap, StorageValue},
+    weights::Weight,
+};
+use rstd::{collections::btree_map::BTreeMap, prelude::Vec};
+use sp_arithmetic::traits::{One, Saturating, UniqueSaturatedInto, Zero};
+use sp_io::hashing::{blake2_256, twox_128};
+
+use crate::reward_sharing::{RewardPerStake, Rewarder, ACCURACY};
+use crate::weights::WeightInfo;
+use crate::*;
+
+/// Version of tables encoding in storage
+#[derive(Decode, Encode, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum StorageVersion {
+    /// Whole table is stored in `Scores` as one value
+    V0,
+    /// Table is split into `Tables`, `TableScores`, `Targets`, `Votes`,
+    /// `Checkpoints` and `VotesByAccount`
+    V1,
+}
+
+impl StorageVersion {
+    pub fn latest() -> Self {
+        StorageVersion::V1
+    }
+}
+
+impl Default for StorageVersion {
+    fn default() -> Self {
+        StorageVersion::V0
+    }
+}
+
+/// Encoding of the first release
+pub mod v0 {
+    use codec::{Decode, Encode};
+    use rstd::collections::{btree_map::BTreeMap, btree_set::BTreeSet};
+
+    use sp_arithmetic::traits::SimpleArithmetic;
+
+    use crate::record::Record;
+    use crate::table::RawString;
+
+    #[derive(Decode, Encode, Default, Eq, PartialEq, Clone)]
+    #[cfg_attr(feature = "std", derive(Debug))]
+    pub struct Rewarder<BalanceType, PeriodType: Ord, VoterId: Ord> {
+        pub current_reward: BalanceType,
+        pub rewards: BTreeMap<PeriodType, BalanceType>,
+        pub origin: BTreeMap<VoterId, PeriodType>,
+    }
+
+    #[derive(Decode, Encode, Default, Eq, PartialEq, Clone)]
+    #[cfg_attr(feature = "std", derive(Debug))]
+    pub struct TargetData<VoterId: Ord, BalanceType, PeriodType: Ord> {
+        pub total: BalanceType,
+        pub votes: BTreeMap<VoterId, BalanceType>,
+        pub rewarder: Rewarder<BalanceType, PeriodType, VoterId>,
+    }
+
+    #[derive(Decode, Encode, Default, Eq, PartialEq, Clone)]
+    #[cfg_attr(feature = "std", derive(Debug))]
+    pub struct Table<
+        AssetId,
+        VoterId: Ord,
+        TargetType: Default + Ord,
+        BalanceType: Default + SimpleArithmetic,
+        PeriodType: Ord,
+        WalletType,
+    > {
+        pub name: Option<RawString>,
+        pub head_count: u8,
+        pub vote_asset: AssetId,
+        pub scores: BTreeSet<Record<TargetType, BalanceType>>,
+        pub targets: BTreeMap<TargetType, TargetData<VoterId, BalanceType, PeriodType>>,
+        pub wallet: WalletType,
+    }
+}
+
+pub(crate) type TableV0<T> = v0::Table<
+    AssetId<T>,
+    AccountId<T>,
+    <T as Trait>::TargetType,
+    Balance<T>,
+    <T as Trait>::PeriodType,
+    AccountId<T>,
+>;
+
+/// Start migration of tables to the latest version and migrate the first of them.
+///
+/// Tables left are migrated by `migrate_step` in next blocks.
+pub fn migrate<T: Trait>() -> Weight {
+    if PalletVersion::get() == StorageVersion::latest() || MigrationCursor::<T>::get().is_some() {
+        return 0;
+    }
+
+    MigrationCursor::<T>::put(T::TableId::zero());
+    migrate_step::<T>()
+}
+
+/// Migrate at most `Trait::MaxMigrationTables` tables from the cursor.
+///
+/// The version is updated after the last table. Until then tables left
+/// aren't found by dispatchables.
+pub fn migrate_step<T: Trait>() -> Weight {
+    if PalletVersion::get() == StorageVersion::latest() {
+        return 0;
+    }
+    let mut table_id = match MigrationCursor::<T>::get() {
+        Some(table_id) => table_id,
+        None => return 0,
+    };
+    let next_table_id = TableIdSequence::<T>::get();
+    let mut weight: Weight = 0;
+
+    // Zero limit would never finish the migration
+    for _ in 0..T::MaxMigrationTables::get().max(1) {
+        if table_id >= next_table_id {
+            break;
+        }
+        weight = weight.saturating_add(migrate_table::<T>(table_id));
+        table_id += One::one();
+    }
+
+    if table_id < next_table_id {
+        MigrationCursor::<T>::put(table_id);
+    } else {
+        MigrationCursor::<T>::kill();
+        PalletVersion::put(StorageVersion::latest());
+    }
+
+    weight
+}
+
+/// Raw storage key of table in `Scores` map of the first release
+pub fn v0_table_key<T: Trait>(table_id: T::TableId) -> Vec<u8> {
+    let mut key = twox_128(Tables::<T>::module_prefix()).to_vec();
+    key.extend_from_slice(&twox_128(b"Scores"));
+    key.extend_from_slice(&blake2_256(&table_id.encode()));
+    key
+}
+
+/// Single-asset amount as map of non-zero amounts by asset
+fn by_asset<A: Ord + Clone, V: Zero>(asset: &A, amount: V) -> BTreeMap<A, V> {
+    let mut map = BTreeMap::new();
+    if !amount.is_zero() {
+        map.insert(asset.clone(), amount);
+    }
+    map
+}
+
+/// Split table of `Scores` into separate maps.
+///
+/// Tables keep their wallet: reward tokens of old tables are reserved on
+/// the creator account, so the creator stays both the wallet and the owner,
+/// and rewards are paid in vote asset. Integer reward per stake is scaled
+/// to fixed-point and reward periods without checkpoints are dropped.
+/// Rewards owed to voters before the upgrade aren't known, so they aren't
+/// counted as unpaid and can't become dust.
+pub(crate) fn migrate_table<T: Trait>(table_id: T::TableId) -> Weight {
+    let table = match unhashed::take::<TableV0<T>>(&v0_table_key::<T>(table_id)) {
+        Some(table) => table,
+        None => return T::WeightInfo::migrate_table(0, 0),
+    };
+    let to_fixed = |reward: Balance<T>| {
+        UniqueSaturatedInto::<RewardPerStake>::unique_saturated_into(reward).saturating_mul(ACCURACY)
+    };
+    let asset = table.vote_asset;
+    let target_count = table.targets.len() as u32;
+    let mut votes = 0u32;
+
+    Tables::<T>::insert(
+        table_id,
+        TableInfo::<T> {
+            name: table.name,
+            head_count: table.head_count,
+            vote_asset: asset,
+            reward_asset: None,
+            wallet: table.wallet.clone(),
+            owner: table.wallet,
+            state: TableState::Active,
+        },
+    );
+    TableScores::<T>::insert(table_id, table.scores);
+
+    for (target, data) in table.targets {
+        let key = (table_id, target.clone());
+        for (voter, vote) in data.votes.iter() {
+            Votes::<T>::insert(&key, voter, vote);
+            VotesByAccount::<T>::mutate(voter, table_id, |targets| targets.insert(target.clone()));
+        }
+        votes = votes.saturating_add(data.votes.len() as u32);
+
+        let mut refs = BTreeMap::new();
+        for (voter, period) in data.rewarder.origin {
+            *refs.entry(period).or_insert(0) += 1;
+            Checkpoints::<T>::insert(&key, voter, period);
+        }
+        let rewards = data
+            .rewarder
+            .rewards
+            .into_iter()
+            .filter(|(period, _)| refs.contains_key(period))
+            .map(|(period, reward)| (period, by_asset(&asset, to_fixed(reward))))
+            .collect();
+
+        Targets::<T>::insert(
+            table_id,
+            target,
+            TargetSummary::<T> {
+                total: data.total,
+                voters: data.votes.len() as u32,
+                rewarder: Rewarder::from_periods(
+                    by_asset(&asset, to_fixed(data.rewarder.current_reward)),
+                    rewards,
+                    refs,
+                    BTreeMap::new(),
+                ),
+                unpaid: BTreeMap::new(),
+            },
+        );
+    }
+
+    T::WeightInfo::migrate_table(target_count, votes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::*;
+    use crate::weights::WeightInfo;
+
+    type TargetType = <Test as Trait>::TargetType;
+    const TARGET1: TargetType = 1;
+    const TARGET2: TargetType = 2;
+
+    /// Table of the first release after the following calls:
+    /// ALICE votes 100 and BOB votes 300 for TARGET1, BOB votes 200 for TARGET2,
+    /// 800 is rewarded to TARGET1, ALICE votes 100 more, 500 is rewarded to TARGET1.
+    fn table_v0() -> TableV0<Test> {
+        let mut targets = BTreeMap::new();
+        targets.insert(
+            TARGET1,
+            v0::TargetData {
+                total: 500,
+                votes: vec![(ALICE, 200), (BOB, 300)].into_iter().collect(),
+                rewarder: v0::Rewarder {
+                    current_reward: 3,
+                    rewards: vec![(0, 0), (1, 0), (2, 2)].into_iter().collect(),
+                    origin: vec![(ALICE, 2), (BOB, 1)].into_iter().collect(),
+                },
+            },
+        );
+        targets.insert(
+            TARGET2,
+            v0::TargetData {
+                total: 200,
+                votes: vec![(BOB, 200)].into_iter().collect(),
+                rewarder: v0::Rewarder {
+                    current_reward: 0,
+                    rewards: vec![(0, 0)].into_iter().collect(),
+                    origin: vec![(BOB, 0)].into_iter().collect(),
+                },
+            },
+        );
+
+        v0::Table {
+            name: Some(b"old".to_vec()),
+            head_count: 2,
+            vote_asset: ASSET_ID,
+            scores: vec![Record::new(TARGET1, 500), Record::new(TARGET2, 200)]
+                .into_iter()
+                .collect(),
+            targets,
+            wallet: ALICE,
+        }
+    }
+
+    #[test]
+    fn from_v0() {
+        new_test_ext().execute_with(|| {
+            TableIdSequence::<Test>::put(2);
+            unhashed::put_raw(&v0_table_key::<Test>(1), &table_v0().encode());
+
+            assert_eq!(
+                migrate::<Test>(),
+                <() as WeightInfo>::migrate_table(0, 0) + <() as WeightInfo>::migrate_table(2, 3)
+            );
+
+            assert_eq!(TablescoreModule::pallet_version(), StorageVersion::latest());
+            assert_eq!(unhashed::get_raw(&v0_table_key::<Test>(1)), None);
+            assert_eq!(TablescoreModule::tables(0), None);
+
+            let info = TablescoreModule::tables(1).unwrap();
+            assert_eq!(info.name, Some(b"old".to_vec()));
+            assert_eq!(info.wallet, ALICE);
+            assert_eq!(info.owner, ALICE);
+            assert_eq!(info.reward_asset, None);
+            assert_eq!(TablescoreModule::head(1), vec![TARGET1, TARGET2]);
+            assert_eq!(TablescoreModule::score_of(1, TARGET1), Some(500));
+            assert_eq!(TablescoreModule::targets(1, TARGET1).map(|t| t.voters), Some(2));
+
+            assert_eq!(TablescoreModule::vote_of(1, TARGET1, ALICE), Some(200));
+            assert_eq!(TablescoreModule::vote_of(1, TARGET1, BOB), Some(300));
+            assert_eq!(TablescoreModule::vote_of(1, TARGET2, BOB), Some(200));
+            assert_eq!(
+                TablescoreModule::votes_by_account(ALICE, 1).into_iter().collect::<Vec<_>>(),
+                vec![TARGET1]
+            );
+            assert_eq!(
+                TablescoreModule::votes_by_account(BOB, 1).into_iter().collect::<Vec<_>>(),
+                vec![TARGET1, TARGET2]
+            );
+
+            assert_eq!(TablescoreModule::pending_reward(1, TARGET1, ALICE), vec![(ASSET_ID, 200)]);
+            assert_eq!(TablescoreModule::pending_reward(1, TARGET1, BOB), vec![(ASSET_ID, 900)]);
+            assert_eq!(TablescoreModule::pending_reward(1, TARGET2, BOB), vec![]);
+            assert_eq!(TablescoreModule::targets(1, TARGET1).map(|t| t.unpaid), Some(0));
+            // Period 0 has no checkpoints after ALICE voted again
+            assert_eq!(TablescoreModule::targets(1, TARGET1).map(|t| t.rewarder.periods()), Some(2));
+            assert_eq!(TablescoreModule::dust(1, ASSET_ID), 0);
+        });
+    }
+
+    #[test]
+    fn in_steps() {
+        new_test_ext().execute_with(|| {
+            TableIdSequence::<Test>::put(3);
+            for table_id in 0..3 {
+                unhashed::put_raw(&v0_table_key::<Test>(table_id), &table_v0().encode());
+            }
+
+            assert_eq!(migrate::<Test>(), 2 * <() as WeightInfo>::migrate_table(2, 3));
+            assert_eq!(TablescoreModule::pallet_version(), StorageVersion::V0);
+            assert_eq!(TablescoreModule::migration_cursor(), Some(2));
+            assert!(TablescoreModule::tables(1).is_some());
+            assert_eq!(TablescoreModule::tables(2), None);
+
+            // Started migration isn't restarted
+            assert_eq!(migrate::<Test>(), 0);
+
+            run_to_block(2);
+            assert_eq!(TablescoreModule::pallet_version(), StorageVersion::latest());
+            assert_eq!(TablescoreModule::migration_cursor(), None);
+            assert_eq!(TablescoreModule::vote_of(2, TARGET1, BOB), Some(300));
+            assert_eq!(migrate_step::<Test>(), 0);
+        });
+    }
+
+    #[test]
+    fn latest_is_kept() {
+        new_test_ext().execute_with(|| {
+            PalletVersion::put(StorageVersion::latest());
+            TableIdSequence::<Test>::put(1);
+            unhashed::put_raw(&v0_table_key::<Test>(0), b"not a table");
+
+            assert_eq!(migrate::<Test>(), 0);
+
+            assert_eq!(TablescoreModule::tables(0), None);
+            assert!(unhashed::get_raw(&v0_table_key::<Test>(0)).is_some());
+        });
+    }
+}
@@ -1,18 +1,30 @@
 // Creating mock runtime here
 
-use crate::{Module, Trait};
-use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
+use crate::{Module, OnHeadChange, Trait};
+use std::cell::RefCell;
+use frame_support::{impl_outer_event, impl_outer_origin, parameter_types, traits::Contains, weights::Weight};
 use sp_core::H256;
 use sp_runtime::{
     testing::Header,
-    traits::{BlakeTwo256, IdentityLookup},
-    Perbill,
+    traits::{BlakeTwo256, Convert, IdentityLookup, OnInitialize},
+    ModuleId, Perbill,
 };
 
 impl_outer_origin! {
     pub enum Origin for Test {}
 }
 
+mod tablescore {
+    pub use crate::Event;
+}
+
+impl_outer_event! {
+    pub enum TestEvent for Test {
+        assets<T>,
+        tablescore<T>,
+    }
+}
+
 // For testing the pallet, we construct most of a mock runtime. This means
 // first constructing a configuration type (`Test`) which `impl`s each of the
 // configuration traits of pallets we want to use.
@@ -35,7 +47,7 @@ impl system::Trait for Test {
     type AccountId = u64;
     type Lookup = IdentityLookup<Self::AccountId>;
     type Header = Header;
-    type Event = ();
+    type Event = TestEvent;
     type BlockHashCount = BlockHashCount;
     type MaximumBlockWeight = MaximumBlockWeight;
     type MaximumBlockLength = MaximumBlockLength;
@@ -45,27 +57,147 @@ impl system::Trait for Test {
 }
 
 impl assets::Trait for Test {
-    type Event = ();
+    type Event = TestEvent;
 
     type Balance = u128;
     type AssetId = u32;
 }
 
+thread_local! {
+    pub static HEAD_CHANGES: RefCell<Vec<(u32, Vec<u32>, Vec<u32>)>> = RefCell::new(Vec::new());
+}
+
+/// Keeps every head change for later checks
+pub struct RecordHeadChange;
+impl OnHeadChange<u32, u32> for RecordHeadChange {
+    fn on_head_change(table_id: u32, entered: &[u32], left: &[u32]) {
+        HEAD_CHANGES.with(|changes| {
+            changes
+                .borrow_mut()
+                .push((table_id, entered.to_vec(), left.to_vec()))
+        });
+    }
+}
+
+/// Account is the target with the same number
+pub struct AccountTarget;
+impl Convert<u64, Option<u32>> for AccountTarget {
+    fn convert(account: u64) -> Option<u32> {
+        Some(account as u32)
+    }
+}
+
+/// ALICE and BOB are members
+pub struct Members;
+impl Contains<u64> for Members {
+    fn contains(who: &u64) -> bool {
+        Self::sorted_members().contains(who)
+    }
+
+    fn sorted_members() -> Vec<u64> {
+        vec![ALICE, BOB]
+    }
+}
+
+parameter_types! {
+    pub const TablescoreModuleId: ModuleId = ModuleId(*b"py/table");
+    pub const MaxTargetsPerTable: u32 = 3;
+    pub const MaxVotersPerTarget: u32 = 2;
+    pub const MaxNameLength: u32 = 16;
+    pub const MaxHeadLen: u8 = 10;
+    pub const MaxRewardAssets: u32 = 2;
+    pub const MaxStreamsPerBlock: u32 = 2;
+    pub const MaxStreamsPerTable: u32 = 3;
+    pub const MinStreamAmount: u128 = 5;
+    pub const MaxDestroyVotes: u32 = 2;
+    pub const MaxMigrationTables: u32 = 2;
+    pub const MaxTableAdmins: u32 = 2;
+    pub const DepositAsset: u32 = DEPOSIT_ASSET_ID;
+    pub const TableDeposit: u128 = TABLE_DEPOSIT;
+    pub const MaxTablesPerAccount: u32 = 3;
+}
+
 impl Trait for Test {
-    type Event = ();
+    type Event = TestEvent;
     type TargetType = u32;
     type TableId = u32;
 
     type PeriodType = u32;
+    type ModuleId = TablescoreModuleId;
+    type OnHeadChange = RecordHeadChange;
+    type WeightInfo = ();
+    type MaxTargetsPerTable = MaxTargetsPerTable;
+    type MaxVotersPerTarget = MaxVotersPerTarget;
+    type MaxNameLength = MaxNameLength;
+    type MaxHeadLen = MaxHeadLen;
+    type MaxRewardAssets = MaxRewardAssets;
+    type MaxStreamsPerBlock = MaxStreamsPerBlock;
+    type MaxStreamsPerTable = MaxStreamsPerTable;
+    type MinStreamAmount = MinStreamAmount;
+    type MaxDestroyVotes = MaxDestroyVotes;
+    type MaxMigrationTables = MaxMigrationTables;
+    type AdminOrigin = system::EnsureRoot<u64>;
+    type MaxTableAdmins = MaxTableAdmins;
+    type CreateTableOrigin = system::EnsureSigned<u64>;
+    type DepositAsset = DepositAsset;
+    type TableDeposit = TableDeposit;
+    type MaxTablesPerAccount = MaxTablesPerAccount;
+    type AccountTarget = AccountTarget;
+    type VoterFilter = Members;
 }
 
+pub type System = system::Module<Test>;
 pub type TablescoreModule = Module<Test>;
+pub type Assets = assets::Module<Test>;
+
+pub const ALICE: <Test as system::Trait>::AccountId = 0;
+pub const BOB: <Test as system::Trait>::AccountId = 1;
+pub const CAROL: <Test as system::Trait>::AccountId = 2;
+pub const ASSET_ID: <Test as assets::Trait>::AssetId = 0;
+pub const REWARD_ASSET_ID: <Test as assets::Trait>::AssetId = 1;
+pub const OTHER_ASSET_ID: <Test as assets::Trait>::AssetId = 2;
+pub const DEPOSIT_ASSET_ID: <Test as assets::Trait>::AssetId = 3;
+pub const TABLE_DEPOSIT: <Test as assets::Trait>::Balance = 100;
+pub const INITIAL_BALANCE: <Test as assets::Trait>::Balance = 1_000_000;
 
 // This function basically just builds a genesis storage key/value store according to
 // our desired mockup.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-    system::GenesisConfig::default()
+    let mut storage = system::GenesisConfig::default()
         .build_storage::<Test>()
-        .unwrap()
-        .into()
+        .unwrap();
+
+    assets::GenesisConfig::<Test> {
+        assets: vec![ASSET_ID, REWARD_ASSET_ID, OTHER_ASSET_ID, DEPOSIT_ASSET_ID],
+        initial_balance: INITIAL_BALANCE,
+        endowed_accounts: vec![ALICE, BOB, CAROL],
+        next_asset_id: 100,
+        staking_asset_id: 16000,
+        spending_asset_id: 16001,
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::from(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
+
+/// Move to block `n` running `on_initialize` of tablescore in every block
+pub fn run_to_block(n: u64) {
+    while System::block_number() < n {
+        System::set_block_number(System::block_number() + 1);
+        TablescoreModule::on_initialize(System::block_number());
+    }
+}
+
+/// Events of tablescore module since the start of block
+pub fn tablescore_events() -> Vec<crate::Event<Test>> {
+    System::events()
+        .into_iter()
+        .filter_map(|record| match record.event {
+            TestEvent::tablescore(event) => Some(event),
+            _ => None,
+        })
+        .collect()
 }
@@ -1,29 +1,91 @@
 use codec::{Decode, Encode};
-use rstd::collections::btree_map::BTreeMap;
-use sp_arithmetic::traits::*;
+use rstd::{collections::btree_map::BTreeMap, prelude::Vec};
+use sp_arithmetic::{helpers_128bit::multiply_by_rational, traits::*};
 
 pub trait RewardSharing {
+    type AssetId;
     type RewardBalance;
     type UserId;
 
-    fn append_reward(&mut self, reward: Self::RewardBalance);
-    fn pop_reward(&mut self, user: &Self::UserId) -> Option<Self::RewardBalance>;
+    /// Add reward in asset, fails without changes if accumulated reward overflows
+    fn append_reward(
+        &mut self,
+        asset: Self::AssetId,
+        reward: Self::RewardBalance,
+    ) -> Result<(), RewardOverflow>;
+
+    /// Rewards of user in every asset, `None` if there is nothing to pay
+    fn pop_reward(
+        &mut self,
+        user: &Self::UserId,
+    ) -> Option<BTreeMap<Self::AssetId, Self::RewardBalance>>;
+}
+
+/// Reward is too big to be accounted
+#[derive(Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RewardOverflow;
+
+/// Reward for one unit of stake in fixed-point with `ACCURACY` scale
+pub type RewardPerStake = u128;
+
+/// Scale of `RewardPerStake`
+pub const ACCURACY: RewardPerStake = 1_000_000_000_000_000_000;
+
+/// Reward of `stake` for `reward_per_stake`, rounded down
+pub fn stake_reward<BalanceType: SimpleArithmetic>(
+    reward_per_stake: RewardPerStake,
+    stake: BalanceType,
+) -> BalanceType {
+    multiply_by_rational(reward_per_stake, stake.unique_saturated_into(), ACCURACY)
+        .map(BalanceType::unique_saturated_from)
+        .unwrap_or_else(|_| BalanceType::max_value())
 }
 
 #[derive(Decode, Encode, Default, Eq, PartialEq, Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub struct Rewarder<BalanceType: SimpleArithmetic, PeriodType: Ord + SimpleArithmetic, VoterId: Ord> {
-    current_reward: BalanceType,
-    rewards: BTreeMap<PeriodType, BalanceType>,
+pub struct Rewarder<
+    BalanceType: SimpleArithmetic,
+    PeriodType: Ord + SimpleArithmetic,
+    VoterId: Ord,
+    AssetId: Ord,
+> {
+    /// Accumulated reward per stake of every asset
+    current_reward: BTreeMap<AssetId, RewardPerStake>,
+    rewards: BTreeMap<PeriodType, BTreeMap<AssetId, RewardPerStake>>,
     origin: BTreeMap<VoterId, PeriodType>,
+
+    /// Count of voters' checkpoints at period, periods without them are removed
+    refs: BTreeMap<PeriodType, u32>,
+
+    /// Part of shared rewards lost to rounding, it's added to the next reward
+    undistributed: BTreeMap<AssetId, BalanceType>,
 }
 
 impl<
         BalanceType: Default + SimpleArithmetic + Copy,
         PeriodType: Default + SimpleArithmetic + Copy,
         VoterId: Ord,
-    > Rewarder<BalanceType, PeriodType, VoterId>
+        AssetId: Ord + Clone,
+    > Rewarder<BalanceType, PeriodType, VoterId, AssetId>
 {
+    /// Restore rewarder from reward periods and count of checkpoints at them
+    /// without the checkpoints themselves
+    pub fn from_periods(
+        current_reward: BTreeMap<AssetId, RewardPerStake>,
+        rewards: BTreeMap<PeriodType, BTreeMap<AssetId, RewardPerStake>>,
+        refs: BTreeMap<PeriodType, u32>,
+        undistributed: BTreeMap<AssetId, BalanceType>,
+    ) -> Self {
+        Rewarder {
+            current_reward,
+            rewards,
+            origin: BTreeMap::new(),
+            refs,
+            undistributed,
+        }
+    }
+
     pub fn get_current_period(&self) -> PeriodType {
         match self.rewards.last_key_value() {
             Some((key, _value)) => *key,
@@ -39,69 +101,342 @@ impl<
     }
 
     pub fn new_voter(&mut self, voter: VoterId) {
-        self.increment_period();
-        self.origin.insert(voter, self.get_current_period());
+        let period = self.checkpoint();
+        self.origin.insert(voter, period);
+    }
+
+    /// Remove checkpoint of voter without stake
+    pub fn remove_voter(&mut self, voter: &VoterId) {
+        if let Some(period) = self.origin.remove(voter) {
+            self.release(period);
+        }
+    }
+
+    /// Count of periods kept for checkpoints
+    pub fn periods(&self) -> usize {
+        self.rewards.len()
+    }
+
+    /// Count of assets that some voter is owed
+    pub fn assets(&self) -> usize {
+        self.current_reward.len()
+    }
+
+    pub fn has_asset(&self, asset: &AssetId) -> bool {
+        self.current_reward.contains_key(asset)
+    }
+
+    /// Take a reference to period with current reward.
+    ///
+    /// New period is opened only if reward was appended since the last one.
+    fn checkpoint(&mut self) -> PeriodType {
+        let current = self.get_current_period();
+        let period = if self.rewards.get(&current) == Some(&self.current_reward) {
+            current
+        } else {
+            let next = self.get_next_period();
+            self.rewards.insert(next, self.current_reward.clone());
+            next
+        };
+        *self.refs.entry(period).or_insert(0) += 1;
+        period
     }
 
-    pub fn increment_period(&mut self) {
-        self.rewards
-            .insert(self.get_next_period(), self.current_reward.clone());
+    /// Drop a reference to period, period without references is removed
+    fn release(&mut self, period: PeriodType) {
+        let unused = match self.refs.get_mut(&period) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        };
+        if unused {
+            self.refs.remove(&period);
+            self.rewards.remove(&period);
+        }
+        self.prune_assets();
+    }
+
+    /// Forget assets that nobody is owed, so they don't take reward asset slots.
+    ///
+    /// An asset is owed while a checkpoint period is behind its current reward
+    /// or a part of it is undistributed.
+    fn prune_assets(&mut self) {
+        let rewards = &self.rewards;
+        let undistributed = &self.undistributed;
+        let settled: Vec<AssetId> = self
+            .current_reward
+            .iter()
+            .filter(|(asset, current)| {
+                undistributed.get(asset).map_or(true, |rest| rest.is_zero())
+                    && rewards
+                        .values()
+                        .all(|reward| reward.get(asset).cloned().unwrap_or(0) == **current)
+            })
+            .map(|(asset, _)| asset.clone())
+            .collect();
+
+        for asset in settled.iter() {
+            self.current_reward.remove(asset);
+            for reward in self.rewards.values_mut() {
+                reward.remove(asset);
+            }
+        }
+    }
+
+    /// Share reward in asset between `total` stake.
+    ///
+    /// Reward per stake is rounded down, the rest of reward is kept in
+    /// `undistributed` and shared with the next reward in the same asset.
+    /// Fails without changes if reward per stake overflows.
+    pub fn share(
+        &mut self,
+        asset: AssetId,
+        reward: BalanceType,
+        total: BalanceType,
+    ) -> Result<(), RewardOverflow> {
+        let reward = reward
+            .checked_add(&self.get_undistributed(&asset))
+            .ok_or(RewardOverflow)?;
+        if total.is_zero() {
+            self.undistributed.insert(asset, reward);
+            return Ok(());
+        }
+
+        let reward_per_stake =
+            multiply_by_rational(reward.unique_saturated_into(), ACCURACY, total.unique_saturated_into())
+                .map_err(|_| RewardOverflow)?;
+        let rest = reward.saturating_sub(stake_reward(reward_per_stake, total));
+
+        self.append_reward(asset.clone(), reward_per_stake)?;
+        if rest.is_zero() {
+            self.undistributed.remove(&asset);
+        } else {
+            self.undistributed.insert(asset, rest);
+        }
+        Ok(())
+    }
+
+    pub fn get_undistributed(&self, asset: &AssetId) -> BalanceType {
+        self.undistributed.get(asset).cloned().unwrap_or_else(Zero::zero)
+    }
+
+    /// Take voters' checkpoints out, the rest of rewarder is shared by all voters
+    pub fn take_origin(&mut self) -> BTreeMap<VoterId, PeriodType> {
+        rstd::mem::replace(&mut self.origin, BTreeMap::new())
+    }
+
+    /// Put back checkpoints of selected voters
+    pub fn with_origin(mut self, origin: BTreeMap<VoterId, PeriodType>) -> Self {
+        self.origin = origin;
+        self
     }
 }
 
 impl<
         BalanceType: Default + Copy + SimpleArithmetic,
         PeriodType: Default + SimpleArithmetic + Copy,
-        VoterId: Ord,
-    > RewardSharing for Rewarder<BalanceType, PeriodType, VoterId>
+        VoterId: Ord + Clone,
+        AssetId: Ord + Clone,
+    > RewardSharing for Rewarder<BalanceType, PeriodType, VoterId, AssetId>
 {
-    type RewardBalance = BalanceType;
+    type AssetId = AssetId;
+    type RewardBalance = RewardPerStake;
     type UserId = VoterId;
 
-    fn append_reward(&mut self, reward: Self::RewardBalance) {
-        self.current_reward += reward;
+    fn append_reward(
+        &mut self,
+        asset: Self::AssetId,
+        reward: Self::RewardBalance,
+    ) -> Result<(), RewardOverflow> {
+        let current = self.current_reward.get(&asset).cloned().unwrap_or(0);
+        let current = current.checked_add(reward).ok_or(RewardOverflow)?;
+        self.current_reward.insert(asset, current);
+        Ok(())
     }
 
-    fn pop_reward(&mut self, user: &Self::UserId) -> Option<Self::RewardBalance> {
-        let next_period = self.get_next_period();
+    fn pop_reward(
+        &mut self,
+        user: &Self::UserId,
+    ) -> Option<BTreeMap<Self::AssetId, Self::RewardBalance>> {
+        let start = *self.origin.get(user)?;
+        let start_reward = self.rewards.get(&start)?;
 
-        match self.origin.get_mut(user) {
-            Some(start) => {
-                let res = self.current_reward - *self.rewards.get(start)?;
-                *start = next_period;
+        let res: BTreeMap<AssetId, RewardPerStake> = self
+            .current_reward
+            .iter()
+            .map(|(asset, reward)| {
+                let start = start_reward.get(asset).cloned().unwrap_or(0);
+                (asset.clone(), reward.saturating_sub(start))
+            })
+            .filter(|(_, reward)| *reward != 0)
+            .collect();
 
-                if res != Zero::zero() {
-                    Some(res)
-                } else {
-                    None
-                }
-            }
-            None => None,
+        if !res.is_empty() {
+            self.release(start);
+            let period = self.checkpoint();
+            self.origin.insert(user.clone(), period);
+            Some(res)
+        } else {
+            None
         }
     }
 }
 
 #[cfg(test)]
 mod tests_reward_sharing {
+    use super::{RewardOverflow, ACCURACY};
     use crate::reward_sharing::RewardSharing;
-    type Rewarder = super::Rewarder<u32, u32, u32>;
+    use rstd::collections::btree_map::BTreeMap;
+    type Rewarder = super::Rewarder<u32, u32, u32, u8>;
 
     const ALICE: u32 = 0;
     const BOB: u32 = 1;
     const CAROL: u32 = 2;
 
+    const ASSET: u8 = 0;
+    const OTHER_ASSET: u8 = 1;
+
+    fn rewards(list: &[(u8, u128)]) -> Option<BTreeMap<u8, u128>> {
+        Some(list.iter().cloned().collect())
+    }
+
     #[test]
     fn simple_sharing() {
         let mut target = Rewarder::default();
         target.new_voter(ALICE);
         target.new_voter(BOB);
-        target.append_reward(5);
+        assert_eq!(target.append_reward(ASSET, 5 * ACCURACY), Ok(()));
 
         target.new_voter(CAROL);
-        target.append_reward(1);
+        assert_eq!(target.append_reward(ASSET, ACCURACY), Ok(()));
+
+        assert_eq!(target.pop_reward(&ALICE), rewards(&[(ASSET, 6 * ACCURACY)]));
+        assert_eq!(target.pop_reward(&BOB), rewards(&[(ASSET, 6 * ACCURACY)]));
+        assert_eq!(target.pop_reward(&CAROL), rewards(&[(ASSET, ACCURACY)]));
+    }
+
+    #[test]
+    fn share_keeps_remainder() {
+        let mut target = Rewarder::default();
+        target.new_voter(ALICE);
+
+        assert_eq!(target.share(ASSET, 2, 3), Ok(()));
+        assert_eq!(target.pop_reward(&ALICE), rewards(&[(ASSET, ACCURACY * 2 / 3)]));
+        assert_eq!(target.get_undistributed(&ASSET), 1);
+
+        assert_eq!(target.share(ASSET, 1, 3), Ok(()));
+        assert_eq!(target.pop_reward(&ALICE), rewards(&[(ASSET, ACCURACY * 2 / 3)]));
+        assert_eq!(target.get_undistributed(&ASSET), 1);
+
+        assert_eq!(target.share(ASSET, 1, 0), Ok(()));
+        assert_eq!(target.pop_reward(&ALICE), None);
+        assert_eq!(target.get_undistributed(&ASSET), 2);
+        assert_eq!(target.get_undistributed(&OTHER_ASSET), 0);
+    }
+
+    #[test]
+    fn share_overflow() {
+        let mut target = Rewarder::default();
+        target.new_voter(ALICE);
+        assert_eq!(target.append_reward(ASSET, u128::max_value() - ACCURACY), Ok(()));
+
+        // Failed reward neither changes reward per stake nor stays undistributed
+        assert_eq!(target.share(ASSET, 2, 1), Err(RewardOverflow));
+        assert_eq!(target.get_undistributed(&ASSET), 0);
+        assert_eq!(target.share(ASSET, 1, 1), Ok(()));
+        assert_eq!(target.pop_reward(&ALICE), rewards(&[(ASSET, u128::max_value())]));
+
+        assert_eq!(target.share(OTHER_ASSET, u32::max_value(), 0), Ok(()));
+        assert_eq!(target.share(OTHER_ASSET, 1, 0), Err(RewardOverflow));
+        assert_eq!(target.get_undistributed(&OTHER_ASSET), u32::max_value());
+    }
+
+    #[test]
+    fn several_assets() {
+        let mut target = Rewarder::default();
+        target.new_voter(ALICE);
+        assert_eq!(target.append_reward(ASSET, 2 * ACCURACY), Ok(()));
+
+        target.new_voter(BOB);
+        assert_eq!(target.append_reward(OTHER_ASSET, 3 * ACCURACY), Ok(()));
+        assert_eq!(target.append_reward(ASSET, ACCURACY), Ok(()));
+
+        assert_eq!(target.assets(), 2);
+        assert_eq!(
+            target.pop_reward(&ALICE),
+            rewards(&[(ASSET, 3 * ACCURACY), (OTHER_ASSET, 3 * ACCURACY)])
+        );
+        assert_eq!(
+            target.pop_reward(&BOB),
+            rewards(&[(ASSET, ACCURACY), (OTHER_ASSET, 3 * ACCURACY)])
+        );
+
+        assert_eq!(target.append_reward(OTHER_ASSET, ACCURACY), Ok(()));
+        assert_eq!(target.pop_reward(&ALICE), rewards(&[(OTHER_ASSET, ACCURACY)]));
+        assert_eq!(target.pop_reward(&ALICE), None);
+    }
+
+    #[test]
+    fn prune_assets() {
+        let mut target = Rewarder::default();
+        target.new_voter(ALICE);
+        target.new_voter(BOB);
+        assert_eq!(target.append_reward(ASSET, ACCURACY), Ok(()));
+        assert_eq!(target.append_reward(OTHER_ASSET, ACCURACY), Ok(()));
+        assert_eq!(target.assets(), 2);
+
+        // Bob is still owed both assets
+        assert_eq!(
+            target.pop_reward(&ALICE),
+            rewards(&[(ASSET, ACCURACY), (OTHER_ASSET, ACCURACY)])
+        );
+        assert_eq!(target.assets(), 2);
+
+        assert_eq!(
+            target.pop_reward(&BOB),
+            rewards(&[(ASSET, ACCURACY), (OTHER_ASSET, ACCURACY)])
+        );
+        assert_eq!(target.assets(), 0);
+
+        // Undistributed part keeps the asset
+        assert_eq!(target.share(ASSET, 1, 3), Ok(()));
+        assert_eq!(target.pop_reward(&ALICE), rewards(&[(ASSET, ACCURACY / 3)]));
+        assert_eq!(target.pop_reward(&BOB), rewards(&[(ASSET, ACCURACY / 3)]));
+        assert_eq!(target.assets(), 1);
+
+        assert_eq!(target.append_reward(OTHER_ASSET, ACCURACY), Ok(()));
+        assert_eq!(target.pop_reward(&ALICE), rewards(&[(OTHER_ASSET, ACCURACY)]));
+        target.remove_voter(&BOB);
+        assert_eq!(target.assets(), 1);
+        assert_eq!(target.pop_reward(&ALICE), None);
+    }
+
+    #[test]
+    fn prune_periods() {
+        let mut target = Rewarder::default();
+        target.new_voter(ALICE);
+
+        for _ in 0..100 {
+            target.new_voter(BOB);
+            assert_eq!(target.append_reward(ASSET, ACCURACY), Ok(()));
+            target.new_voter(CAROL);
+            assert_eq!(target.append_reward(ASSET, ACCURACY), Ok(()));
+
+            assert_eq!(target.pop_reward(&BOB), rewards(&[(ASSET, 2 * ACCURACY)]));
+            target.remove_voter(&BOB);
+            assert_eq!(target.pop_reward(&CAROL), rewards(&[(ASSET, ACCURACY)]));
+            target.remove_voter(&CAROL);
+            assert!(target.periods() <= 3);
+        }
+
+        assert_eq!(target.pop_reward(&ALICE), rewards(&[(ASSET, 200 * ACCURACY)]));
+        assert_eq!(target.periods(), 1);
+        assert_eq!(target.origin.len(), 1);
 
-        assert_eq!(target.pop_reward(&ALICE), Some(6));
-        assert_eq!(target.pop_reward(&BOB), Some(6));
-        assert_eq!(target.pop_reward(&CAROL), Some(1));
+        target.remove_voter(&ALICE);
+        assert_eq!(target.periods(), 0);
+        assert!(target.refs.is_empty());
     }
 }
@@ -0,0 +1,41 @@
+use codec::{Decode, Encode};
+
+use crate::table::HeadRewardPolicy;
+
+/// Id of reward stream
+pub type StreamId = u64;
+
+/// Who gets payments of reward stream
+#[derive(Decode, Encode, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum StreamRecipient<TargetType> {
+    /// Single target of table
+    Target(TargetType),
+    /// Targets in head of table at the moment of payment
+    Head(HeadRewardPolicy),
+}
+
+/// Reward paid to table every `period` blocks from tokens escrowed by sponsor
+#[derive(Decode, Encode, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RewardStream<TableId, TargetType, AccountId, AssetId, BalanceType, BlockNumber> {
+    pub table_id: TableId,
+    pub recipient: StreamRecipient<TargetType>,
+
+    /// Creator of stream, the rest of escrow is refunded to them
+    pub sponsor: AccountId,
+
+    /// Reward asset of table at the moment of creation
+    pub asset: AssetId,
+    pub amount_per_period: BalanceType,
+    pub period: BlockNumber,
+
+    /// Block of the next payment
+    pub next: BlockNumber,
+
+    /// Last block at which payment can be scheduled
+    pub end: BlockNumber,
+
+    /// Escrow left in reserved balance of table wallet
+    pub remaining: BalanceType,
+}
@@ -0,0 +1,39 @@
+//! Runtime API definition for tablescore pallet
+
+use codec::Codec;
+use rstd::prelude::Vec;
+
+sp_api::decl_runtime_apis! {
+    pub trait TablescoreApi<TableId, TargetType, AccountId, AssetId, Balance>
+    where
+        TableId: Codec,
+        TargetType: Codec,
+        AccountId: Codec,
+        AssetId: Codec,
+        Balance: Codec,
+    {
+        /// Wallet that keeps reward tokens of table
+        fn wallet(table_id: TableId) -> Option<AccountId>;
+
+        /// Targets with the biggest vote-balance in table
+        fn head(table_id: TableId) -> Vec<TargetType>;
+
+        /// Total vote-balance of target
+        fn score_of(table_id: TableId, target: TargetType) -> Option<Balance>;
+
+        /// Place of target in table, the leader has rank 1
+        fn rank_of(table_id: TableId, target: TargetType) -> Option<u32>;
+
+        /// Vote-balance of voter for target
+        fn vote_of(table_id: TableId, target: TargetType, voter: AccountId) -> Option<Balance>;
+
+        /// Vote-balances of account for every target it voted for in every table
+        fn votes_of_account(voter: AccountId) -> Vec<(TableId, TargetType, Balance)>;
+
+        /// Rewards by asset that voter can pick up for target
+        fn pending_reward(table_id: TableId, target: TargetType, voter: AccountId) -> Vec<(AssetId, Balance)>;
+
+        /// Reward in asset of removed targets that no voter can pick up
+        fn dust(table_id: TableId, asset: AssetId) -> Balance;
+    }
+}
@@ -6,14 +6,17 @@ use crate::record::*;
 use crate::reward_sharing::RewardSharing;
 use crate::table_data::*;
 use codec::{Decode, Encode};
-use sp_arithmetic::traits::{SimpleArithmetic, Zero};
+use sp_arithmetic::{
+    helpers_128bit::multiply_by_rational,
+    traits::{Saturating, SimpleArithmetic, UniqueSaturatedFrom, UniqueSaturatedInto, Zero},
+};
 
 pub type RawString = Vec<u8>;
 
 #[derive(Decode, Encode, Default, Eq, PartialEq, Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct Table<
-    AssetId: Default + Encode + Decode,
+    AssetId: Default + Ord + Clone + Encode + Decode,
     VoterId: Default + Ord + Encode + Decode + Clone,
     TargetType: Default + Ord + Encode + Decode,
     BalanceType: Default + Copy + SimpleArithmetic + Zero + Encode + Decode,
@@ -26,21 +29,131 @@ pub struct Table<
     /// Count for method `get_head`
     head_count: u8,
 
-    /// Asset for vote and reward
+    /// Asset for vote
     pub vote_asset: AssetId,
 
+    /// Asset for reward, vote asset is used if it's not set
+    pub reward_asset: Option<AssetId>,
+
     /// Sorted target set for look at head
     pub scores: BTreeSet<Record<TargetType, BalanceType>>,
 
     /// Targets data with voter map, total vote-balance and reward info
-    pub targets: BTreeMap<TargetType, TargetData<VoterId, BalanceType, PeriodType>>,
+    pub targets: BTreeMap<TargetType, TargetData<VoterId, BalanceType, PeriodType, AssetId>>,
+
+    /// Wallet for lock reward tokens before send
+    pub wallet: WalletType,
+
+    /// Bounds checked by method `vote`
+    limits: Limits,
+
+    /// Unpaid reward of targets removed from table, no voter can pick it up
+    pub dust: Rewards<AssetId, BalanceType>,
+
+    /// Stage of table lifecycle, only active table takes votes
+    pub state: TableState,
+}
+
+/// Upper bounds for count of targets in table and voters of target
+#[derive(Decode, Encode, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Limits {
+    pub max_targets: u32,
+    pub max_voters: u32,
+    pub max_reward_assets: u32,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_targets: u32::max_value(),
+            max_voters: u32::max_value(),
+            max_reward_assets: u32::max_value(),
+        }
+    }
+}
+
+/// Stage of table lifecycle
+#[derive(Decode, Encode, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum TableState {
+    /// Table takes votes and rewards
+    Active,
+    /// Table doesn't take votes, voters can still unvote and pick up rewards
+    Frozen,
+    /// Votes of table are cancelled one by one, after that the table is removed
+    Destroying,
+}
+
+impl Default for TableState {
+    fn default() -> Self {
+        TableState::Active
+    }
+}
+
+/// Split of reward between targets of table head
+#[derive(Decode, Encode, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum HeadRewardPolicy {
+    /// Every target of head gets the same share
+    Equal,
+    /// Share of target is proportional to its vote-balance
+    Proportional,
+}
+
+/// Accounts which can vote in table
+#[derive(Decode, Encode, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum VoterPolicy {
+    /// Any account with vote asset
+    Open,
+    /// Accounts added to allow-list of table by its owner
+    AllowList,
+    /// Accounts passed by `Trait::VoterFilter`
+    Members,
+}
+
+impl Default for VoterPolicy {
+    fn default() -> Self {
+        VoterPolicy::Open
+    }
+}
+
+/// Table settings stored apart from targets and votes
+#[derive(Decode, Encode, Default, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TableInfo<AssetId, WalletType, OwnerId> {
+    /// Optional name for table
+    pub name: Option<RawString>,
+
+    /// Count for head of table
+    pub head_count: u8,
+
+    /// Asset for vote
+    pub vote_asset: AssetId,
+
+    /// Asset for reward, vote asset is used if it's not set
+    pub reward_asset: Option<AssetId>,
 
     /// Wallet for lock reward tokens before send
     pub wallet: WalletType,
+
+    /// Creator of table or account it was transferred to
+    pub owner: OwnerId,
+
+    /// Stage of table lifecycle
+    pub state: TableState,
+}
+
+impl<AssetId, WalletType, OwnerId> TableInfo<AssetId, WalletType, OwnerId> {
+    /// Asset in which rewards are appended and paid
+    pub fn reward_asset(&self) -> &AssetId {
+        self.reward_asset.as_ref().unwrap_or(&self.vote_asset)
+    }
 }
 
 impl<
-        AssetId: Default + Encode + Decode,
+        AssetId: Default + Ord + Clone + Encode + Decode,
         VoterId: Default + Ord + Encode + Decode + Clone,
         TargetType: Default + Ord + Clone + Encode + Decode,
         BalanceType: Default + Copy + SimpleArithmetic + Clone + Encode + Decode,
@@ -58,12 +171,51 @@ impl<
             name,
             head_count,
             vote_asset,
+            reward_asset: None,
             wallet,
             scores: BTreeSet::default(),
             targets: BTreeMap::default(),
+            limits: Limits::default(),
+            dust: BTreeMap::new(),
+            state: TableState::Active,
         }
     }
 
+    /// Asset in which rewards are appended and paid
+    pub fn reward_asset(&self) -> &AssetId {
+        self.reward_asset.as_ref().unwrap_or(&self.vote_asset)
+    }
+
+    /// Bound count of targets and voters for next votes
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Create table with settings from info and already sorted scores
+    pub fn from_info<OwnerId>(
+        info: TableInfo<AssetId, WalletType, OwnerId>,
+        scores: BTreeSet<Record<TargetType, BalanceType>>,
+    ) -> Self {
+        Table {
+            name: info.name,
+            head_count: info.head_count,
+            vote_asset: info.vote_asset,
+            reward_asset: info.reward_asset,
+            wallet: info.wallet,
+            scores,
+            targets: BTreeMap::default(),
+            limits: Limits::default(),
+            dust: BTreeMap::new(),
+            state: info.state,
+        }
+    }
+
+    /// Change count of targets in head
+    pub fn set_head_count(&mut self, head_count: u8) {
+        self.head_count = head_count;
+    }
+
     fn update_record(
         &mut self,
         target: TargetType,
@@ -85,11 +237,11 @@ impl<
         balance: BalanceType,
         is_insert: bool,
         callback: F,
-    ) -> VoteResult<BalanceType, BalanceType>
+    ) -> VoteResult<BalanceType, Rewards<AssetId, BalanceType>>
     where
         F: FnOnce(
-            &mut TargetData<VoterId, BalanceType, PeriodType>,
-        ) -> VoteResult<BalanceType, BalanceType>,
+            &mut TargetData<VoterId, BalanceType, PeriodType, AssetId>,
+        ) -> VoteResult<BalanceType, Rewards<AssetId, BalanceType>>,
     {
         let (result, old_balance, new_balance) = match self.targets.get_mut(&target) {
             Some(data) => {
@@ -99,7 +251,9 @@ impl<
                 (res, old_balance, data.total.clone())
             }
             None => {
-                if is_insert && balance != Zero::zero() {
+                if is_insert && self.scores.len() >= self.limits.max_targets as usize {
+                    (VoteResult::TargetsLimit, Zero::zero(), balance)
+                } else if is_insert && balance != Zero::zero() {
                     self.targets.insert(
                         target.clone(),
                         TargetData::create_with_first_vote(account.clone(), balance.clone()),
@@ -112,15 +266,20 @@ impl<
         };
 
         match &result {
-            VoteResult::VoteNotFound => {}
             VoteResult::Unvoted(_unvoted, _reward) => {
                 if new_balance == Zero::zero() {
-                    self.targets.remove(&target);
+                    if let Some(data) = self.targets.remove(&target) {
+                        for (asset, unpaid) in data.unpaid {
+                            let dust = self.dust.entry(asset).or_insert_with(Zero::zero);
+                            *dust = dust.saturating_add(unpaid);
+                        }
+                    }
                 }
 
                 self.update_record(target, old_balance, new_balance);
             }
-            _ => self.update_record(target, old_balance, new_balance),
+            VoteResult::Success(_) => self.update_record(target, old_balance, new_balance),
+            _ => {}
         }
 
         result
@@ -131,9 +290,21 @@ impl<
         target: TargetType,
         voter: &VoterId,
         balance: BalanceType,
-    ) -> VoteResult<BalanceType, BalanceType> {
+    ) -> VoteResult<BalanceType, Rewards<AssetId, BalanceType>> {
+        if self.state != TableState::Active {
+            return VoteResult::TableFrozen;
+        }
+        if balance.is_zero() {
+            return VoteResult::ZeroVote;
+        }
+
+        let max_voters = self.limits.max_voters;
         self.process(target, voter, balance.clone(), true, |td| {
-            td.vote(voter.clone(), balance)
+            if !td.votes.contains_key(voter) && td.voters >= max_voters {
+                VoteResult::VotersLimit
+            } else {
+                td.vote(voter.clone(), balance)
+            }
         })
     }
 
@@ -142,7 +313,7 @@ impl<
         target: TargetType,
         voter: &VoterId,
         balance: BalanceType,
-    ) -> VoteResult<BalanceType, BalanceType> {
+    ) -> VoteResult<BalanceType, Rewards<AssetId, BalanceType>> {
         self.process(target, voter, balance.clone(), false, |td| {
             td.unvote(voter, balance)
         })
@@ -152,7 +323,7 @@ impl<
         &mut self,
         target: TargetType,
         account: &VoterId,
-    ) -> VoteResult<BalanceType, BalanceType> {
+    ) -> VoteResult<BalanceType, Rewards<AssetId, BalanceType>> {
         self.process(target, account, Zero::zero(), false, |td| {
             td.cancel(account)
         })
@@ -166,18 +337,77 @@ impl<
             .collect()
     }
 
-    pub fn pop_reward(&mut self, user: &VoterId, target: TargetType) -> Option<BalanceType> {
+    /// Shares of `reward` for targets of head by `policy`, rounded down.
+    ///
+    /// The rest left by rounding goes to the leader, so shares sum up to
+    /// `reward`. Targets with zero share are skipped.
+    pub fn head_shares(
+        &self,
+        reward: BalanceType,
+        policy: HeadRewardPolicy,
+    ) -> Vec<(TargetType, BalanceType)> {
+        let head: Vec<&Record<TargetType, BalanceType>> =
+            self.scores.iter().take(self.head_count as usize).collect();
+        let head_total = head
+            .iter()
+            .fold(BalanceType::zero(), |total, record| total.saturating_add(record.balance));
+
+        let mut shares: Vec<(TargetType, BalanceType)> = head
+            .iter()
+            .map(|record| {
+                let share = match policy {
+                    HeadRewardPolicy::Equal => reward / BalanceType::from(head.len() as u32),
+                    HeadRewardPolicy::Proportional => multiply_by_rational(
+                        reward.unique_saturated_into(),
+                        record.balance.unique_saturated_into(),
+                        head_total.unique_saturated_into(),
+                    )
+                    .map(BalanceType::unique_saturated_from)
+                    .unwrap_or_else(|_| Zero::zero()),
+                };
+                (record.get_target().clone(), share)
+            })
+            .collect();
+
+        let shared = shares
+            .iter()
+            .fold(BalanceType::zero(), |total, (_, share)| total.saturating_add(*share));
+        if let Some((_, leader_share)) = shares.first_mut() {
+            *leader_share = leader_share.saturating_add(reward.saturating_sub(shared));
+        }
+
+        shares.retain(|(_, share)| !share.is_zero());
+        shares
+    }
+
+    pub fn pop_reward(
+        &mut self,
+        user: &VoterId,
+        target: TargetType,
+    ) -> Option<Rewards<AssetId, BalanceType>> {
         self.targets
             .get_mut(&target)
             .and_then(|data| data.pop_reward(user))
     }
 
-    pub fn append_reward(&mut self, target: TargetType, reward: BalanceType) -> Result<(), ()> {
-        if let Some(data) = self.targets.get_mut(&target) {
-            data.append_reward(reward);
-            Ok(())
-        } else {
-            Err(())
+    pub fn append_reward(
+        &mut self,
+        target: TargetType,
+        asset: AssetId,
+        reward: BalanceType,
+    ) -> VoteResult<BalanceType, Rewards<AssetId, BalanceType>> {
+        match self.targets.get_mut(&target) {
+            Some(data)
+                if !data.rewarder.has_asset(&asset)
+                    && data.rewarder.assets() >= self.limits.max_reward_assets as usize =>
+            {
+                VoteResult::RewardAssetsLimit
+            }
+            Some(data) => match data.append_reward(asset, reward) {
+                Ok(()) => VoteResult::Success(None),
+                Err(_) => VoteResult::RewardOverflow,
+            },
+            None => VoteResult::TargetNotFound,
         }
     }
 }
@@ -185,7 +415,7 @@ impl<
 #[cfg(test)]
 mod tests {
     type Table = super::Table<u8, u8, u8, u32, u32, u8>;
-    type VR = super::VoteResult<u32, u32>;
+    type VR = super::VoteResult<u32, super::Rewards<u8, u32>>;
 
     const ALICE: u8 = 10;
     const BOB: u8 = 11;
@@ -193,6 +423,11 @@ mod tests {
     const CAROL: u8 = 13;
 
     const WALLET: u8 = 0;
+    const ASSET: u8 = 0;
+
+    fn rewards(amount: u32) -> super::Rewards<u8, u32> {
+        Some((ASSET, amount)).into_iter().collect()
+    }
 
     fn compare_head(table: &Table, expected: Vec<u8>) {
         assert_eq!(
@@ -286,5 +521,108 @@ mod tests {
         compare_head(&table, vec![2, 1]);
     }
 
-    // ToDo add reward sharing tests
+    #[test]
+    fn dust() {
+        let mut table = Table::new(None, 2, 0, WALLET);
+
+        assert_eq!(table.vote(0, &ALICE, 300), VR::Success(None));
+        assert_eq!(table.vote(0, &BOB, 700), VR::Success(None));
+        assert_eq!(table.append_reward(0, ASSET, 5), VR::Success(None));
+        assert_eq!(table.append_reward(1, ASSET, 5), VR::TargetNotFound);
+
+        assert_eq!(table.cancel(0, &ALICE), VR::Unvoted(300, Some(rewards(1))));
+        assert!(table.dust.is_empty());
+        assert_eq!(table.cancel(0, &BOB), VR::Unvoted(700, Some(rewards(3))));
+        assert_eq!(table.dust, rewards(1));
+    }
+
+    #[test]
+    fn reward_asset_slots() {
+        let mut table = Table::new(None, 2, 0, WALLET).with_limits(super::Limits {
+            max_targets: 2,
+            max_voters: 2,
+            max_reward_assets: 1,
+        });
+
+        assert_eq!(table.vote(0, &ALICE, 10), VR::Success(None));
+        assert_eq!(table.vote(0, &BOB, 10), VR::Success(None));
+        assert_eq!(table.append_reward(0, ASSET, 10), VR::Success(None));
+        assert_eq!(table.append_reward(0, ASSET + 1, 10), VR::RewardAssetsLimit);
+
+        // The slot is taken until every voter picks up the reward
+        assert_eq!(table.pop_reward(&ALICE, 0), Some(rewards(5)));
+        assert_eq!(table.append_reward(0, ASSET + 1, 10), VR::RewardAssetsLimit);
+        assert_eq!(table.cancel(0, &BOB), VR::Unvoted(10, Some(rewards(5))));
+        assert_eq!(table.append_reward(0, ASSET + 1, 10), VR::Success(None));
+    }
+
+    #[test]
+    fn limits() {
+        let mut table = Table::new(None, 2, 0, WALLET).with_limits(super::Limits {
+            max_targets: 2,
+            max_voters: 2,
+            max_reward_assets: 1,
+        });
+
+        assert_eq!(table.vote(0, &ALICE, 10), VR::Success(None));
+        assert_eq!(table.vote(1, &BOB, 11), VR::Success(None));
+        assert_eq!(table.vote(2, &CARL, 12), VR::TargetsLimit);
+
+        assert_eq!(table.vote(0, &BOB, 10), VR::Success(None));
+        assert_eq!(table.vote(0, &CARL, 10), VR::VotersLimit);
+        assert_eq!(table.vote(0, &ALICE, 10), VR::Success(None));
+        compare_head(&table, vec![0, 1]);
+
+        assert_eq!(table.cancel(1, &BOB), VR::Unvoted(11, None));
+        assert_eq!(table.vote(2, &CARL, 12), VR::Success(None));
+        assert_eq!(table.cancel(0, &BOB), VR::Unvoted(10, None));
+        assert_eq!(table.vote(0, &CARL, 10), VR::Success(None));
+        compare_head(&table, vec![0, 2]);
+
+        assert_eq!(table.append_reward(0, ASSET, 10), VR::Success(None));
+        assert_eq!(table.append_reward(0, ASSET + 1, 10), VR::RewardAssetsLimit);
+        assert_eq!(table.append_reward(2, ASSET + 1, 10), VR::Success(None));
+    }
+
+    #[test]
+    fn head_shares() {
+        use super::HeadRewardPolicy::{Equal, Proportional};
+
+        let mut table = Table::new(None, 2, 0, WALLET);
+        assert_eq!(table.head_shares(100, Equal), vec![]);
+
+        assert_eq!(table.vote(0, &ALICE, 300), VR::Success(None));
+        assert_eq!(table.vote(1, &BOB, 100), VR::Success(None));
+        assert_eq!(table.vote(2, &CARL, 50), VR::Success(None));
+
+        assert_eq!(table.head_shares(101, Equal), vec![(0, 51), (1, 50)]);
+        assert_eq!(table.head_shares(101, Proportional), vec![(0, 76), (1, 25)]);
+        assert_eq!(table.head_shares(1, Proportional), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn frozen() {
+        let mut table = Table::new(None, 2, 0, WALLET);
+
+        assert_eq!(table.vote(0, &ALICE, 10), VR::Success(None));
+        assert_eq!(table.vote(0, &BOB, 10), VR::Success(None));
+
+        table.state = super::TableState::Frozen;
+        assert_eq!(table.vote(0, &ALICE, 10), VR::TableFrozen);
+        assert_eq!(table.vote(1, &CARL, 10), VR::TableFrozen);
+        assert_eq!(table.unvote(0, &ALICE, 5), VR::Unvoted(5, None));
+        assert_eq!(table.cancel(0, &BOB), VR::Unvoted(10, None));
+        compare_head(&table, vec![0]);
+    }
+
+    #[test]
+    fn zero_vote() {
+        let mut table = Table::new(None, 2, 0, WALLET);
+
+        assert_eq!(table.vote(0, &ALICE, 10), VR::Success(None));
+        assert_eq!(table.vote(0, &BOB, 0), VR::ZeroVote);
+        assert_eq!(table.vote(1, &BOB, 0), VR::ZeroVote);
+        assert_eq!(table.targets.get(&0).map(|data| data.voters), Some(1));
+        compare_head(&table, vec![0]);
+    }
 }
@@ -1,9 +1,12 @@
 use codec::{Decode, Encode};
 use core::cmp::Ordering;
 use rstd::collections::btree_map::BTreeMap;
-use sp_arithmetic::traits::{SimpleArithmetic, Zero};
+use sp_arithmetic::traits::{Saturating, SimpleArithmetic, Zero};
 
-use crate::reward_sharing::{RewardSharing, Rewarder};
+use crate::reward_sharing::{stake_reward, RewardOverflow, RewardSharing, Rewarder};
+
+/// Amounts of reward by asset
+pub type Rewards<AssetId, BalanceType> = BTreeMap<AssetId, BalanceType>;
 
 #[derive(Decode, Encode, Default, Eq, PartialEq, Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -11,11 +14,37 @@ pub struct TargetData<
     VoterId: Default + Ord + Clone,
     BalanceType: Default + Copy + SimpleArithmetic + Zero,
     PeriodType: Default + SimpleArithmetic + Copy,
+    AssetId: Ord,
 > {
     pub total: BalanceType,
     pub votes: BTreeMap<VoterId, BalanceType>,
 
-    pub rewarder: Rewarder<BalanceType, PeriodType, VoterId>,
+    /// Count of voters, `votes` may keep only part of them
+    pub voters: u32,
+
+    pub rewarder: Rewarder<BalanceType, PeriodType, VoterId, AssetId>,
+
+    /// Reward appended and not paid to voters yet
+    pub unpaid: Rewards<AssetId, BalanceType>,
+}
+
+/// Part of target data shared by all voters of the target
+#[derive(Decode, Encode, Default, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TargetSummary<
+    VoterId: Ord,
+    BalanceType: SimpleArithmetic,
+    PeriodType: Ord + SimpleArithmetic,
+    AssetId: Ord,
+> {
+    pub total: BalanceType,
+    pub voters: u32,
+
+    /// Reward periods, checkpoints of voters are stored apart
+    pub rewarder: Rewarder<BalanceType, PeriodType, VoterId, AssetId>,
+
+    /// Reward appended and not paid to voters yet
+    pub unpaid: Rewards<AssetId, BalanceType>,
 }
 
 #[derive(PartialEq)]
@@ -24,41 +53,85 @@ pub enum VoteResult<VoteType, RewardType> {
     Success(Option<RewardType>),
     Unvoted(VoteType, Option<RewardType>),
     VoteNotFound,
+    TargetNotFound,
+    TargetsLimit,
+    VotersLimit,
+    RewardAssetsLimit,
+    TableFrozen,
+    ZeroVote,
+    RewardOverflow,
 }
 
 impl<
         VoterId: Default + Ord + Clone,
         BalanceType: Default + Copy + SimpleArithmetic + Zero + Clone,
         PeriodType: Default + SimpleArithmetic + Copy,
-    > TargetData<VoterId, BalanceType, PeriodType>
+        AssetId: Default + Ord + Clone,
+    > TargetData<VoterId, BalanceType, PeriodType, AssetId>
 {
     pub fn create_with_first_vote(first_voter: VoterId, balance: BalanceType) -> Self {
         let mut res = TargetData {
             total: balance.clone(),
             votes: BTreeMap::new(),
+            voters: 1,
             rewarder: Rewarder::default(),
+            unpaid: BTreeMap::new(),
         };
         res.votes.insert(first_voter.clone(), balance);
         res.rewarder.new_voter(first_voter);
         res
     }
 
+    /// Restore target data from summary and the state of selected voters
+    pub fn from_parts(
+        summary: TargetSummary<VoterId, BalanceType, PeriodType, AssetId>,
+        votes: BTreeMap<VoterId, BalanceType>,
+        origin: BTreeMap<VoterId, PeriodType>,
+    ) -> Self {
+        TargetData {
+            total: summary.total,
+            votes,
+            voters: summary.voters,
+            rewarder: summary.rewarder.with_origin(origin),
+            unpaid: summary.unpaid,
+        }
+    }
+
+    /// Split target data into summary, votes and voters' reward checkpoints
+    pub fn into_parts(
+        self,
+    ) -> (
+        TargetSummary<VoterId, BalanceType, PeriodType, AssetId>,
+        BTreeMap<VoterId, BalanceType>,
+        BTreeMap<VoterId, PeriodType>,
+    ) {
+        let mut rewarder = self.rewarder;
+        let origin = rewarder.take_origin();
+        (
+            TargetSummary {
+                total: self.total,
+                voters: self.voters,
+                rewarder,
+                unpaid: self.unpaid,
+            },
+            self.votes,
+            origin,
+        )
+    }
+
     pub fn vote(
         &mut self,
         account: VoterId,
         votes: BalanceType,
-    ) -> VoteResult<BalanceType, BalanceType> {
+    ) -> VoteResult<BalanceType, Rewards<AssetId, BalanceType>> {
         self.total += votes.clone();
-        if let Some(user_balance) = self.votes.get_mut(&account) {
-            let res = match self.rewarder.pop_reward(&account) {
-                Some(reward) => VoteResult::Success(Some(reward * *user_balance)),
-                _ => VoteResult::Success(None),
-            };
-            *user_balance += votes;
-            self.rewarder.increment_period();
+        if let Some(stake) = self.votes.get(&account).cloned() {
+            let res = VoteResult::Success(self.payout(&account, stake));
+            self.votes.insert(account, stake + votes);
             res
         } else {
             self.votes.insert(account.clone(), votes);
+            self.voters += 1;
             self.rewarder.new_voter(account);
             VoteResult::Success(None)
         }
@@ -68,20 +141,14 @@ impl<
         &mut self,
         account: &VoterId,
         balance: BalanceType,
-    ) -> VoteResult<BalanceType, BalanceType> {
-        if let Some(user_balance) = self.votes.get_mut(&account) {
-            match balance.cmp(user_balance) {
+    ) -> VoteResult<BalanceType, Rewards<AssetId, BalanceType>> {
+        if let Some(stake) = self.votes.get(account).cloned() {
+            match balance.cmp(&stake) {
                 Ordering::Greater | Ordering::Equal => self.cancel(account),
                 Ordering::Less => {
                     self.total -= balance.clone();
-                    let res = VoteResult::Unvoted(
-                        balance,
-                        self.rewarder
-                            .pop_reward(account)
-                            .map(|rew| rew * *user_balance),
-                    );
-                    *user_balance -= balance.clone();
-                    self.rewarder.increment_period();
+                    let res = VoteResult::Unvoted(balance, self.payout(account, stake));
+                    self.votes.insert(account.clone(), stake - balance);
                     res
                 }
             }
@@ -90,52 +157,101 @@ impl<
         }
     }
 
-    pub fn cancel(&mut self, account: &VoterId) -> VoteResult<BalanceType, BalanceType> {
+    pub fn cancel(&mut self, account: &VoterId) -> VoteResult<BalanceType, Rewards<AssetId, BalanceType>> {
         match self.votes.remove(account) {
             Some(balance) => {
-                self.rewarder.increment_period();
                 self.total -= balance.clone();
-                VoteResult::Unvoted(
-                    balance,
-                    self.rewarder.pop_reward(account).map(|rew| rew * balance),
-                )
+                self.voters = self.voters.saturating_sub(1);
+                let reward = self.payout(account, balance);
+                self.rewarder.remove_voter(account);
+                VoteResult::Unvoted(balance, reward)
             }
             None => VoteResult::VoteNotFound,
         }
     }
+
+    /// Pop rewards of voter with `stake` and count them as paid, zero reward is no reward
+    fn payout(
+        &mut self,
+        account: &VoterId,
+        stake: BalanceType,
+    ) -> Option<Rewards<AssetId, BalanceType>> {
+        let rewards: Rewards<AssetId, BalanceType> = self
+            .rewarder
+            .pop_reward(account)?
+            .into_iter()
+            .map(|(asset, reward)| (asset, stake_reward(reward, stake)))
+            .filter(|(_, reward)| !reward.is_zero())
+            .collect();
+
+        for (asset, reward) in rewards.iter() {
+            let rest = self.get_unpaid(asset).saturating_sub(*reward);
+            if rest.is_zero() {
+                self.unpaid.remove(asset);
+            } else {
+                self.unpaid.insert(asset.clone(), rest);
+            }
+        }
+
+        if rewards.is_empty() {
+            None
+        } else {
+            Some(rewards)
+        }
+    }
+
+    pub fn get_unpaid(&self, asset: &AssetId) -> BalanceType {
+        self.unpaid.get(asset).cloned().unwrap_or_else(Zero::zero)
+    }
 }
 
 impl<
         VoterId: Default + Ord + Clone,
         BalanceType: Default + Copy + SimpleArithmetic + Zero + Clone,
         PeriodType: Default + SimpleArithmetic + Copy,
-    > RewardSharing for TargetData<VoterId, BalanceType, PeriodType>
+        AssetId: Default + Ord + Clone,
+    > RewardSharing for TargetData<VoterId, BalanceType, PeriodType, AssetId>
 {
+    type AssetId = AssetId;
     type RewardBalance = BalanceType;
     type UserId = VoterId;
 
-    fn append_reward(&mut self, reward: Self::RewardBalance) {
-        self.rewarder.append_reward(reward / self.total.clone());
+    fn append_reward(
+        &mut self,
+        asset: Self::AssetId,
+        reward: Self::RewardBalance,
+    ) -> Result<(), RewardOverflow> {
+        let unpaid = self.get_unpaid(&asset).checked_add(&reward).ok_or(RewardOverflow)?;
+        self.rewarder.share(asset.clone(), reward, self.total.clone())?;
+        self.unpaid.insert(asset, unpaid);
+        Ok(())
     }
 
-    fn pop_reward(&mut self, user: &Self::UserId) -> Option<Self::RewardBalance> {
-        self.rewarder
-            .pop_reward(user)
-            .and_then(|rew| self.votes.get(&user).map(|count| *count * rew))
+    fn pop_reward(&mut self, user: &Self::UserId) -> Option<Rewards<AssetId, BalanceType>> {
+        let stake = self.votes.get(&user).cloned()?;
+        self.payout(user, stake)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use rstd::collections::btree_map::BTreeMap;
-    type Data = super::TargetData<usize, u32, u32>;
-    type VR = super::VoteResult<u32, u32>;
+    type Data = super::TargetData<usize, u32, u32, u8>;
+    type Rewards = super::Rewards<u8, u32>;
+    type VR = super::VoteResult<u32, Rewards>;
     use super::RewardSharing;
 
     const ALICE: usize = 10;
     const BOB: usize = 11;
     const CARL: usize = 12;
 
+    const ASSET: u8 = 0;
+    const OTHER_ASSET: u8 = 1;
+
+    fn rewards(amount: u32) -> Rewards {
+        Some((ASSET, amount)).into_iter().collect()
+    }
+
     macro_rules! vote_assert
     {
         ($data:ident, $( ($user:ident, $balance:expr) ), * ) => {
@@ -152,11 +268,7 @@ mod tests {
             $(
                 expected.insert($user, $balance + *$data.votes.get(&$user).unwrap_or(&0));
 
-                assert_eq!($data.vote($user, $balance), match $reward
-                    {
-                        Some(reward) => VR::Success(Some(reward)),
-                        None => VR::Success(None),
-                    });
+                assert_eq!($data.vote($user, $balance), VR::Success($reward.map(rewards)));
             )*
             assert_eq!(expected, $data.votes);
         }
@@ -184,6 +296,7 @@ mod tests {
         let data = Data::create_with_first_vote(ALICE, 100);
         assert_eq!(data.total, 100);
         assert_eq!(data.votes.len(), 1);
+        assert_eq!(data.voters, 1);
     }
 
     #[test]
@@ -196,9 +309,9 @@ mod tests {
     fn reward() {
         let mut data = Data::default();
         vote_assert!(data, (ALICE, 200), (BOB, 400), (CARL, 400));
-        data.append_reward(1000);
+        assert_eq!(data.append_reward(ASSET, 1000), Ok(()));
 
-        assert_eq!(data.pop_reward(&ALICE), Some(200));
+        assert_eq!(data.pop_reward(&ALICE), Some(rewards(200)));
 
         vote_assert!(
             data,
@@ -212,15 +325,19 @@ mod tests {
     fn unvote() {
         let mut data = Data::default();
         vote_assert!(data, (ALICE, 400), (BOB, 400), (CARL, 400));
-        data.append_reward(1200);
+        assert_eq!(data.append_reward(ASSET, 1200), Ok(()));
 
-        unvote_part_assert!(data, (ALICE, 200, Some(400)), (BOB, 200, Some(400)));
+        unvote_part_assert!(
+            data,
+            (ALICE, 200, Some(rewards(400))),
+            (BOB, 200, Some(rewards(400)))
+        );
 
-        data.append_reward(800);
+        assert_eq!(data.append_reward(ASSET, 800), Ok(()));
 
-        assert_eq!(data.pop_reward(&ALICE), Some(200));
-        assert_eq!(data.pop_reward(&BOB), Some(200));
-        assert_eq!(data.pop_reward(&CARL), Some(400 + 400));
+        assert_eq!(data.pop_reward(&ALICE), Some(rewards(200)));
+        assert_eq!(data.pop_reward(&BOB), Some(rewards(200)));
+        assert_eq!(data.pop_reward(&CARL), Some(rewards(400 + 400)));
     }
 
     #[test]
@@ -230,25 +347,126 @@ mod tests {
 
         assert_eq!(data.vote(ALICE, 100), VR::Success(None));
         assert_eq!(data.vote(BOB, 100), VR::Success(None));
-        data.append_reward(42123);
+        assert_eq!(data.append_reward(ASSET, 42123), Ok(()));
 
-        assert_eq!(data.vote(ALICE, 100), VR::Success(Some(3500)));
-        data.append_reward(12423);
+        assert_eq!(data.vote(ALICE, 100), VR::Success(Some(rewards(3510))));
+        assert_eq!(data.append_reward(ASSET, 12423), Ok(()));
 
-        assert_eq!(data.vote(BOB, 100), VR::Success(Some(4400)));
-        data.append_reward(20423);
+        assert_eq!(data.vote(BOB, 100), VR::Success(Some(rewards(4465))));
+        assert_eq!(data.append_reward(ASSET, 20423), Ok(()));
 
-        assert_eq!(data.cancel(&ALICE), VR::Unvoted(200, Some(4600)));
-        data.append_reward(20423);
+        assert_eq!(data.cancel(&ALICE), VR::Unvoted(200, Some(rewards(4828))));
+        assert_eq!(data.append_reward(ASSET, 20423), Ok(()));
 
-        assert_eq!(data.cancel(&BOB), VR::Unvoted(200, Some(6200)));
-        data.append_reward(20423);
+        assert_eq!(data.cancel(&BOB), VR::Unvoted(200, Some(rewards(6321))));
+        assert_eq!(data.append_reward(ASSET, 20423), Ok(()));
 
-        assert_eq!(data.pop_reward(&CARL), Some(95000));
+        assert_eq!(data.pop_reward(&CARL), Some(rewards(96690)));
         assert_eq!(data.cancel(&CARL), VR::Unvoted(1000, None));
 
         assert_eq!(data.pop_reward(&ALICE), None);
         assert_eq!(data.pop_reward(&BOB), None);
         assert_eq!(data.pop_reward(&CARL), None);
     }
+
+    #[test]
+    fn parts() {
+        let mut data = Data::default();
+        vote_assert!(data, (ALICE, 400), (BOB, 400));
+        assert_eq!(data.append_reward(ASSET, 800), Ok(()));
+
+        let (summary, votes, origin) = data.clone().into_parts();
+        assert_eq!(
+            Data::from_parts(summary.clone(), votes.clone(), origin.clone()),
+            data
+        );
+
+        let mut alice_only = Data::from_parts(
+            summary,
+            votes.into_iter().filter(|(voter, _)| *voter == ALICE).collect(),
+            origin.into_iter().filter(|(voter, _)| *voter == ALICE).collect(),
+        );
+        assert_eq!(alice_only.pop_reward(&ALICE), data.pop_reward(&ALICE));
+        assert_eq!(alice_only.pop_reward(&BOB), None);
+    }
+
+    #[test]
+    fn small_reward() {
+        let mut data = Data::default();
+        vote_assert!(data, (ALICE, 300), (BOB, 500), (CARL, 200));
+
+        // Reward is less than total stake
+        assert_eq!(data.append_reward(ASSET, 7), Ok(()));
+        assert_eq!(data.rewarder.get_undistributed(&ASSET), 0);
+        assert_eq!(data.pop_reward(&ALICE), Some(rewards(2)));
+        assert_eq!(data.pop_reward(&BOB), Some(rewards(3)));
+
+        assert_eq!(data.append_reward(ASSET, 993), Ok(()));
+        assert_eq!(data.pop_reward(&ALICE), Some(rewards(297)));
+        assert_eq!(data.pop_reward(&BOB), Some(rewards(496)));
+        assert_eq!(data.pop_reward(&CARL), Some(rewards(200)));
+        assert_eq!(data.get_unpaid(&ASSET), 2);
+    }
+
+    #[test]
+    fn unpaid() {
+        let mut data = Data::default();
+        vote_assert!(data, (ALICE, 300), (BOB, 700));
+
+        assert_eq!(data.append_reward(ASSET, 5), Ok(()));
+        assert_eq!(data.get_unpaid(&ASSET), 5);
+
+        assert_eq!(data.cancel(&ALICE), VR::Unvoted(300, Some(rewards(1))));
+        assert_eq!(data.cancel(&BOB), VR::Unvoted(700, Some(rewards(3))));
+        assert_eq!(data.get_unpaid(&ASSET), 1);
+    }
+
+    #[test]
+    fn bounded_size() {
+        use codec::Encode;
+
+        let mut data = Data::default();
+        vote_assert!(data, (ALICE, 100));
+
+        let mut size = None;
+        for _ in 0..100 {
+            assert_eq!(data.vote(BOB, 100), VR::Success(None));
+            assert_eq!(data.append_reward(ASSET, 200), Ok(()));
+            assert_eq!(data.unvote(&BOB, 50), VR::Unvoted(50, Some(rewards(100))));
+            assert_eq!(data.append_reward(ASSET, 150), Ok(()));
+            assert_eq!(data.cancel(&BOB), VR::Unvoted(50, Some(rewards(50))));
+
+            assert_eq!(data.votes.len(), 1);
+            assert_eq!(data.voters, 1);
+            let encoded = data.encode().len();
+            assert!(encoded <= *size.get_or_insert(encoded));
+        }
+
+        assert_eq!(data.pop_reward(&ALICE), Some(rewards(100 * (100 + 100))));
+        assert_eq!(data.rewarder.periods(), 1);
+    }
+
+    #[test]
+    fn several_assets() {
+        let mut data = Data::default();
+        vote_assert!(data, (ALICE, 100), (BOB, 300));
+        assert_eq!(data.append_reward(ASSET, 400), Ok(()));
+        assert_eq!(data.append_reward(OTHER_ASSET, 40), Ok(()));
+
+        assert_eq!(
+            data.pop_reward(&ALICE),
+            Some(vec![(ASSET, 100), (OTHER_ASSET, 10)].into_iter().collect())
+        );
+
+        assert_eq!(data.append_reward(OTHER_ASSET, 40), Ok(()));
+        assert_eq!(
+            data.cancel(&ALICE),
+            VR::Unvoted(100, Some(vec![(OTHER_ASSET, 10)].into_iter().collect()))
+        );
+        assert_eq!(
+            data.pop_reward(&BOB),
+            Some(vec![(ASSET, 300), (OTHER_ASSET, 60)].into_iter().collect())
+        );
+        assert!(data.unpaid.is_empty());
+    }
 }
@@ -1,52 +1,60 @@
-use crate::{mock::*, Trait, VoteResult};
+use crate::{
+    mock::*, Error, HeadRewardPolicy, RawEvent, StreamRecipient, TableState, Tables, Trait, VoteResult, VoterPolicy,
+};
+use codec::Encode;
 
-use frame_support::assert_ok;
+use frame_support::{assert_noop, assert_ok, StorageMap};
+use sp_runtime::traits::OnInitialize;
 
 const HEAD_COUNT: u8 = 10;
 
-const ALICE: <Test as system::Trait>::AccountId = 0;
-const BOB: <Test as system::Trait>::AccountId = 1;
-const ASSET_ID: <Test as assets::Trait>::AssetId = 0;
-
 type TargetType = <Test as Trait>::TargetType;
 const TARGET1: TargetType = 1;
 const TARGET2: TargetType = 2;
 const TARGET3: TargetType = 3;
 
+fn create_table() -> <Test as Trait>::TableId {
+    let table_id = TablescoreModule::next_table_id();
+    assert_ok!(TablescoreModule::create_table(
+        Origin::signed(ALICE),
+        ASSET_ID,
+        HEAD_COUNT,
+        None,
+        None
+    ));
+    table_id
+}
+
 #[test]
 fn create() {
     new_test_ext().execute_with(|| {
-        let table = TablescoreModule::next_table_id();
-        assert_ok!(TablescoreModule::create_table(
-            Origin::signed(ALICE),
-            ASSET_ID,
-            HEAD_COUNT,
-            None
-        ));
+        let table_id = create_table();
 
-        let table = TablescoreModule::tables(table);
+        let table = TablescoreModule::tables(table_id).unwrap();
 
         assert_eq!(table.name, None);
+        assert_eq!(table.head_count, HEAD_COUNT);
         assert_eq!(table.vote_asset, ASSET_ID);
-        assert_eq!(table.wallet, ALICE);
+        assert_eq!(table.reward_asset, None);
+        assert_eq!(table.wallet, TablescoreModule::table_wallet(table_id));
+        assert_eq!(table.owner, ALICE);
+        assert_eq!(TablescoreModule::wallet(table_id), Some(table.wallet));
+        assert_eq!(TablescoreModule::wallet(table_id + 1), None);
 
-        assert_eq!(table.scores.len(), 0);
-        assert_eq!(table.targets.len(), 0);
+        assert_eq!(TablescoreModule::scores(table_id).len(), 0);
+        assert_eq!(TablescoreModule::head(table_id).len(), 0);
     });
 }
 
 #[test]
 fn vote() {
     new_test_ext().execute_with(|| {
-        let table = TablescoreModule::next_table_id();
-        assert_ok!(TablescoreModule::create_table(
-            Origin::signed(ALICE),
-            ASSET_ID,
-            HEAD_COUNT,
-            None
-        ));
+        let table_id = create_table();
 
-        let mut table = TablescoreModule::tables(table);
+        let mut table = crate::Table::<Test>::from_info(
+            TablescoreModule::tables(table_id).unwrap(),
+            TablescoreModule::scores(table_id),
+        );
         assert_eq!(table.vote(TARGET1, &ALICE, 102), VoteResult::Success(None));
         assert_eq!(table.vote(TARGET2, &BOB, 101), VoteResult::Success(None));
         assert_eq!(table.vote(TARGET3, &ALICE, 100), VoteResult::Success(None));
@@ -82,3 +90,1208 @@ fn vote() {
         assert_eq!(table.unvote(TARGET2, &BOB, 1), VoteResult::VoteNotFound);
     });
 }
+
+#[test]
+fn vote_storage() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 102, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 101, TARGET2));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 50, TARGET1));
+
+        assert_eq!(TablescoreModule::head(table_id), vec![TARGET1, TARGET2]);
+        assert_eq!(TablescoreModule::targets(table_id, TARGET1).map(|t| t.total), Some(152));
+        assert_eq!(TablescoreModule::votes((table_id, TARGET1), ALICE), Some(102));
+        assert_eq!(TablescoreModule::votes((table_id, TARGET1), BOB), Some(50));
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &BOB), 151);
+
+        assert_ok!(TablescoreModule::cancel(Origin::signed(BOB), table_id, TARGET2));
+        assert_eq!(TablescoreModule::targets(table_id, TARGET2), None);
+        assert_eq!(TablescoreModule::votes((table_id, TARGET2), BOB), None);
+        assert_eq!(TablescoreModule::head(table_id), vec![TARGET1]);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &BOB), 50);
+    });
+}
+
+#[test]
+fn reward() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 300, TARGET1));
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(ALICE), table_id, 400, TARGET1, None));
+
+        let wallet = TablescoreModule::table_wallet(table_id);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &ALICE), INITIAL_BALANCE - 100 - 400);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 400);
+
+        assert_ok!(TablescoreModule::pop_reward(Origin::signed(BOB), table_id, TARGET1));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &BOB), INITIAL_BALANCE - 300 + 300);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 100);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &wallet), 0);
+
+        assert_ok!(TablescoreModule::pop_reward(Origin::signed(BOB), table_id, TARGET1));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &BOB), INITIAL_BALANCE);
+    });
+}
+
+#[test]
+fn table_not_found() {
+    new_test_ext().execute_with(|| {
+        let table_id = TablescoreModule::next_table_id();
+
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1),
+            Error::<Test>::TableNotFound
+        );
+        assert_noop!(
+            TablescoreModule::unvote(Origin::signed(ALICE), table_id, 100, TARGET1),
+            Error::<Test>::TableNotFound
+        );
+        assert_noop!(
+            TablescoreModule::cancel(Origin::signed(ALICE), table_id, TARGET1),
+            Error::<Test>::TableNotFound
+        );
+        assert_noop!(
+            TablescoreModule::append_reward(Origin::signed(ALICE), table_id, 100, TARGET1, None),
+            Error::<Test>::TableNotFound
+        );
+        assert_noop!(
+            TablescoreModule::pop_reward(Origin::signed(ALICE), table_id, TARGET1),
+            Error::<Test>::TableNotFound
+        );
+
+        assert_eq!(Assets::free_balance(&ASSET_ID, &ALICE), INITIAL_BALANCE);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &ALICE), 0);
+    });
+}
+
+#[test]
+fn zero_vote() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+
+        // Zero stake must not take one of MaxVotersPerTarget slots
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(ALICE), table_id, 0, TARGET1),
+            Error::<Test>::ZeroVote
+        );
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET1));
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &ALICE), 0);
+    });
+}
+
+#[test]
+fn failed_vote_keeps_balances() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(ALICE), table_id, INITIAL_BALANCE + 1, TARGET1),
+            assets::Error::<Test>::InsufficientBalance
+        );
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(ALICE), table_id, 0, TARGET1),
+            Error::<Test>::ZeroVote
+        );
+
+        assert_eq!(Assets::free_balance(&ASSET_ID, &ALICE), INITIAL_BALANCE);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &ALICE), 0);
+        assert_eq!(TablescoreModule::targets(table_id, TARGET1), None);
+    });
+}
+
+#[test]
+fn failed_unvote_keeps_balances() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+
+        assert_noop!(
+            TablescoreModule::unvote(Origin::signed(BOB), table_id, 100, TARGET1),
+            Error::<Test>::VoteNotFound
+        );
+        assert_noop!(
+            TablescoreModule::cancel(Origin::signed(BOB), table_id, TARGET1),
+            Error::<Test>::VoteNotFound
+        );
+        assert_noop!(
+            TablescoreModule::cancel(Origin::signed(ALICE), table_id, TARGET2),
+            Error::<Test>::VoteNotFound
+        );
+
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &ALICE), 100);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &BOB), 0);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &BOB), INITIAL_BALANCE);
+    });
+}
+
+#[test]
+fn failed_append_reward_keeps_balances() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+        let wallet = TablescoreModule::table_wallet(table_id);
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+
+        assert_noop!(
+            TablescoreModule::append_reward(Origin::signed(BOB), table_id, 100, TARGET2, None),
+            Error::<Test>::TargetNotFound
+        );
+        assert_noop!(
+            TablescoreModule::append_reward(Origin::signed(BOB), table_id, INITIAL_BALANCE + 1, TARGET1, None),
+            assets::Error::<Test>::InsufficientBalance
+        );
+
+        assert_eq!(Assets::free_balance(&ASSET_ID, &BOB), INITIAL_BALANCE);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &wallet), 0);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 0);
+    });
+}
+
+#[test]
+fn failed_payout_keeps_balances() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+        let wallet = TablescoreModule::table_wallet(table_id);
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(BOB), table_id, 100, TARGET1, None));
+
+        // Tokens of wallet are gone outside of the pallet
+        assets::Module::<Test>::unreserve(&ASSET_ID, &wallet, 100);
+        assert_ok!(assets::Module::<Test>::make_transfer(&ASSET_ID, &wallet, &BOB, 100));
+
+        assert_noop!(
+            TablescoreModule::pop_reward(Origin::signed(ALICE), table_id, TARGET1),
+            Error::<Test>::WalletBalanceLow
+        );
+        assert_noop!(
+            TablescoreModule::cancel(Origin::signed(ALICE), table_id, TARGET1),
+            Error::<Test>::WalletBalanceLow
+        );
+
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &ALICE), 100);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &ALICE), INITIAL_BALANCE - 100);
+    });
+}
+
+#[test]
+fn runtime_api() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 300, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 200, TARGET2));
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(ALICE), table_id, 800, TARGET1, None));
+
+        assert_eq!(TablescoreModule::head(table_id), vec![TARGET1, TARGET2]);
+        assert_eq!(TablescoreModule::score_of(table_id, TARGET1), Some(400));
+        assert_eq!(TablescoreModule::score_of(table_id, TARGET3), None);
+        assert_eq!(TablescoreModule::rank_of(table_id, TARGET1), Some(1));
+        assert_eq!(TablescoreModule::rank_of(table_id, TARGET2), Some(2));
+        assert_eq!(TablescoreModule::rank_of(table_id, TARGET3), None);
+        assert_eq!(TablescoreModule::vote_of(table_id, TARGET1, BOB), Some(300));
+        assert_eq!(TablescoreModule::vote_of(table_id, TARGET3, BOB), None);
+
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, ALICE), vec![(ASSET_ID, 200)]);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, BOB), vec![(ASSET_ID, 600)]);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET2, BOB), vec![]);
+
+        // Query doesn't take the reward
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, BOB), vec![(ASSET_ID, 600)]);
+    });
+}
+
+#[test]
+fn events() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 300, TARGET1));
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(BOB), table_id, 800, TARGET1, None));
+        assert_ok!(TablescoreModule::unvote(Origin::signed(BOB), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::pop_reward(Origin::signed(ALICE), table_id, TARGET1));
+        assert_ok!(TablescoreModule::cancel(Origin::signed(ALICE), table_id, TARGET1));
+        assert_ok!(TablescoreModule::cancel(Origin::signed(BOB), table_id, TARGET1));
+
+        assert_eq!(
+            tablescore_events(),
+            vec![
+                RawEvent::TableCreated(table_id, ALICE, ASSET_ID, HEAD_COUNT, None, None),
+                RawEvent::Voted(table_id, TARGET1, ALICE, 100, 100),
+                RawEvent::HeadChanged(table_id, vec![TARGET1], vec![]),
+                RawEvent::Voted(table_id, TARGET1, BOB, 300, 400),
+                RawEvent::RewardAppended(table_id, TARGET1, BOB, ASSET_ID, 800),
+                RawEvent::Unvoted(table_id, TARGET1, BOB, 100, 300),
+                RawEvent::RewardPaid(table_id, TARGET1, BOB, ASSET_ID, 600),
+                RawEvent::RewardPaid(table_id, TARGET1, ALICE, ASSET_ID, 200),
+                RawEvent::VoteCancelled(table_id, TARGET1, ALICE, 100, 200),
+                RawEvent::VoteCancelled(table_id, TARGET1, BOB, 200, 0),
+                RawEvent::TargetRemoved(table_id, TARGET1),
+                RawEvent::HeadChanged(table_id, vec![], vec![TARGET1]),
+            ]
+        );
+    });
+}
+
+#[test]
+fn head_changes() {
+    new_test_ext().execute_with(|| {
+        let table_id = TablescoreModule::next_table_id();
+        assert_ok!(TablescoreModule::create_table(Origin::signed(ALICE), ASSET_ID, 2, None, None));
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 200, TARGET2));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 50, TARGET3));
+        // Order inside head doesn't matter
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 150, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 300, TARGET3));
+        assert_ok!(TablescoreModule::cancel(Origin::signed(BOB), table_id, TARGET3));
+
+        let expected = vec![
+            (table_id, vec![TARGET1], vec![]),
+            (table_id, vec![TARGET2], vec![]),
+            (table_id, vec![TARGET3], vec![TARGET2]),
+            (table_id, vec![TARGET2], vec![TARGET3]),
+        ];
+        assert_eq!(HEAD_CHANGES.with(|changes| changes.borrow().clone()), expected);
+
+        let events: Vec<_> = tablescore_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                RawEvent::HeadChanged(table, entered, left) => Some((table, entered, left)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(events, expected);
+    });
+}
+
+#[test]
+fn limits() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            TablescoreModule::create_table(Origin::signed(ALICE), ASSET_ID, MaxHeadLen::get() + 1, None, None),
+            Error::<Test>::HeadTooLong
+        );
+        assert_noop!(
+            TablescoreModule::create_table(
+                Origin::signed(ALICE),
+                ASSET_ID,
+                HEAD_COUNT,
+                Some(vec![0; MaxNameLength::get() as usize + 1]),
+                None
+            ),
+            Error::<Test>::NameTooLong
+        );
+
+        let table_id = create_table();
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET2));
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET3));
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET3 + 1),
+            Error::<Test>::TooManyTargets
+        );
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET1));
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET1),
+            Error::<Test>::TooManyVoters
+        );
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET1));
+
+        assert_ok!(TablescoreModule::cancel(Origin::signed(ALICE), table_id, TARGET3));
+        assert_ok!(TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET3 + 1));
+        assert_ok!(TablescoreModule::cancel(Origin::signed(BOB), table_id, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET1));
+        assert_eq!(TablescoreModule::targets(table_id, TARGET1).map(|t| t.voters), Some(2));
+    });
+}
+
+#[test]
+fn dust() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+        let wallet = TablescoreModule::table_wallet(table_id);
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 300, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 700, TARGET1));
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 5, TARGET1, None));
+
+        assert_ok!(TablescoreModule::cancel(Origin::signed(ALICE), table_id, TARGET1));
+        assert_eq!(TablescoreModule::dust(table_id, ASSET_ID), 0);
+        assert_ok!(TablescoreModule::cancel(Origin::signed(BOB), table_id, TARGET1));
+
+        // 1 of ALICE and 3 of BOB are paid, the rest is lost to rounding
+        assert_eq!(TablescoreModule::dust(table_id, ASSET_ID), 1);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 1);
+
+        assert_noop!(
+            TablescoreModule::sweep_dust(Origin::signed(BOB), table_id, ASSET_ID, BOB),
+            Error::<Test>::NotOwner
+        );
+        assert_noop!(
+            TablescoreModule::sweep_dust(Origin::signed(ALICE), table_id + 1, ASSET_ID, ALICE),
+            Error::<Test>::TableNotFound
+        );
+
+        assert_ok!(TablescoreModule::sweep_dust(Origin::signed(ALICE), table_id, ASSET_ID, CAROL));
+        assert_eq!(TablescoreModule::dust(table_id, ASSET_ID), 0);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 0);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &wallet), 0);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &CAROL), INITIAL_BALANCE - 5 + 1);
+        assert_eq!(tablescore_events().pop(), Some(RawEvent::DustSwept(table_id, CAROL, ASSET_ID, 1)));
+
+        assert_ok!(TablescoreModule::sweep_dust(Origin::ROOT, table_id, ASSET_ID, CAROL));
+    });
+}
+
+#[test]
+fn bounded_storage() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+
+        let mut size = None;
+        for _ in 0..50 {
+            assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET1));
+            assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 200, TARGET1, None));
+            assert_ok!(TablescoreModule::cancel(Origin::signed(BOB), table_id, TARGET1));
+
+            assert_eq!(TablescoreModule::votes((table_id, TARGET1), BOB), None);
+            assert_eq!(TablescoreModule::checkpoints((table_id, TARGET1), BOB), None);
+
+            let summary = TablescoreModule::targets(table_id, TARGET1).unwrap();
+            assert_eq!(summary.rewarder.periods(), 1);
+            let encoded = summary.encode().len();
+            assert_eq!(encoded, *size.get_or_insert(encoded));
+        }
+
+        assert_eq!(Assets::free_balance(&ASSET_ID, &BOB), INITIAL_BALANCE + 50 * 100);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, ALICE), vec![(ASSET_ID, 50 * 100)]);
+    });
+}
+
+#[test]
+fn reward_asset() {
+    new_test_ext().execute_with(|| {
+        let table_id = TablescoreModule::next_table_id();
+        assert_ok!(TablescoreModule::create_table(
+            Origin::signed(ALICE),
+            ASSET_ID,
+            HEAD_COUNT,
+            None,
+            Some(REWARD_ASSET_ID)
+        ));
+        let wallet = TablescoreModule::table_wallet(table_id);
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 300, TARGET1));
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 800, TARGET1, None));
+
+        assert_eq!(Assets::free_balance(&REWARD_ASSET_ID, &CAROL), INITIAL_BALANCE - 800);
+        assert_eq!(Assets::reserved_balance(&REWARD_ASSET_ID, &wallet), 800);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &CAROL), INITIAL_BALANCE);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 0);
+
+        assert_ok!(TablescoreModule::pop_reward(Origin::signed(ALICE), table_id, TARGET1));
+        assert_ok!(TablescoreModule::cancel(Origin::signed(BOB), table_id, TARGET1));
+
+        assert_eq!(Assets::free_balance(&REWARD_ASSET_ID, &ALICE), INITIAL_BALANCE + 200);
+        assert_eq!(Assets::free_balance(&REWARD_ASSET_ID, &BOB), INITIAL_BALANCE + 600);
+        assert_eq!(Assets::reserved_balance(&REWARD_ASSET_ID, &wallet), 0);
+
+        // Vote tokens only get reserved and unreserved
+        assert_eq!(Assets::free_balance(&ASSET_ID, &ALICE), INITIAL_BALANCE - 100);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &ALICE), 100);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &BOB), INITIAL_BALANCE);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &BOB), 0);
+    });
+}
+
+#[test]
+fn several_reward_assets() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+        let wallet = TablescoreModule::table_wallet(table_id);
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 300, TARGET1));
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 400, TARGET1, None));
+        assert_ok!(TablescoreModule::append_reward(
+            Origin::signed(CAROL),
+            table_id,
+            40,
+            TARGET1,
+            Some(OTHER_ASSET_ID)
+        ));
+        assert_noop!(
+            TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 40, TARGET1, Some(REWARD_ASSET_ID)),
+            Error::<Test>::TooManyRewardAssets
+        );
+
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 400);
+        assert_eq!(Assets::reserved_balance(&OTHER_ASSET_ID, &wallet), 40);
+        assert_eq!(
+            TablescoreModule::pending_reward(table_id, TARGET1, ALICE),
+            vec![(ASSET_ID, 100), (OTHER_ASSET_ID, 10)]
+        );
+
+        assert_ok!(TablescoreModule::pop_reward(Origin::signed(ALICE), table_id, TARGET1));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &ALICE), INITIAL_BALANCE - 100 + 100);
+        assert_eq!(Assets::free_balance(&OTHER_ASSET_ID, &ALICE), INITIAL_BALANCE + 10);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, ALICE), vec![]);
+
+        let paid: Vec<_> = tablescore_events()
+            .into_iter()
+            .filter(|event| match event {
+                RawEvent::RewardPaid(..) => true,
+                _ => false,
+            })
+            .collect();
+        assert_eq!(
+            paid,
+            vec![
+                RawEvent::RewardPaid(table_id, TARGET1, ALICE, ASSET_ID, 100),
+                RawEvent::RewardPaid(table_id, TARGET1, ALICE, OTHER_ASSET_ID, 10),
+            ]
+        );
+    });
+}
+
+#[test]
+fn head_reward() {
+    new_test_ext().execute_with(|| {
+        let table_id = TablescoreModule::next_table_id();
+        assert_ok!(TablescoreModule::create_table(Origin::signed(ALICE), ASSET_ID, 2, None, None));
+        let wallet = TablescoreModule::table_wallet(table_id);
+
+        assert_noop!(
+            TablescoreModule::append_head_reward(Origin::signed(CAROL), table_id, 100, HeadRewardPolicy::Equal),
+            Error::<Test>::EmptyHead
+        );
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 300, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET2));
+        assert_ok!(TablescoreModule::vote(Origin::signed(CAROL), table_id, 50, TARGET3));
+
+        // TARGET2 can't take one more asset, so only TARGET1 is rewarded and charged
+        for asset in [OTHER_ASSET_ID, REWARD_ASSET_ID].iter() {
+            assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 10, TARGET2, Some(*asset)));
+        }
+        assert_ok!(TablescoreModule::append_head_reward(Origin::signed(CAROL), table_id, 100, HeadRewardPolicy::Equal));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &CAROL), INITIAL_BALANCE - 50 - 50);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 50);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, ALICE), vec![(ASSET_ID, 50)]);
+        assert_eq!(
+            tablescore_events().last(),
+            Some(&RawEvent::RewardAppended(table_id, TARGET1, CAROL, ASSET_ID, 50))
+        );
+        assert_ok!(TablescoreModule::cancel(Origin::signed(BOB), table_id, TARGET2));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET2));
+
+        assert_ok!(TablescoreModule::append_head_reward(
+            Origin::signed(CAROL),
+            table_id,
+            101,
+            HeadRewardPolicy::Proportional
+        ));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &CAROL), INITIAL_BALANCE - 50 - 50 - 101);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 151);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, ALICE), vec![(ASSET_ID, 126)]);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET2, BOB), vec![(ASSET_ID, 25)]);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET3, CAROL), vec![]);
+
+        let events = tablescore_events();
+        assert_eq!(
+            events[events.len() - 2..].to_vec(),
+            vec![
+                RawEvent::RewardAppended(table_id, TARGET1, CAROL, ASSET_ID, 76),
+                RawEvent::RewardAppended(table_id, TARGET2, CAROL, ASSET_ID, 25),
+            ]
+        );
+
+        assert_ok!(TablescoreModule::append_head_reward(Origin::signed(CAROL), table_id, 100, HeadRewardPolicy::Equal));
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, ALICE), vec![(ASSET_ID, 176)]);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET2, BOB), vec![(ASSET_ID, 75)]);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 251);
+    });
+}
+
+#[test]
+fn reward_stream() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+        let wallet = TablescoreModule::table_wallet(table_id);
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 300, TARGET1));
+
+        // Payments at blocks 11, 21 and 31
+        assert_ok!(TablescoreModule::create_reward_stream(
+            Origin::signed(CAROL),
+            table_id,
+            StreamRecipient::Target(TARGET1),
+            40,
+            10,
+            35
+        ));
+        assert_eq!(tablescore_events().pop(), Some(RawEvent::StreamCreated(0, table_id, CAROL, 120)));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &CAROL), INITIAL_BALANCE - 120);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &wallet), 0);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 120);
+        assert_eq!(TablescoreModule::streams_due(11), vec![0]);
+
+        run_to_block(11);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &wallet), 0);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 120);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, ALICE), vec![(ASSET_ID, 10)]);
+        assert_eq!(TablescoreModule::reward_streams(0).map(|stream| stream.remaining), Some(80));
+
+        run_to_block(31);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &wallet), 0);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 120);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, ALICE), vec![(ASSET_ID, 30)]);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, BOB), vec![(ASSET_ID, 90)]);
+        assert_eq!(TablescoreModule::reward_streams(0), None);
+        assert_eq!(tablescore_events().pop(), Some(RawEvent::StreamClosed(0, CAROL, 0)));
+    });
+}
+
+#[test]
+fn cancel_reward_stream() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET2));
+        assert_ok!(TablescoreModule::create_reward_stream(
+            Origin::signed(CAROL),
+            table_id,
+            StreamRecipient::Head(HeadRewardPolicy::Equal),
+            50,
+            5,
+            100
+        ));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &CAROL), INITIAL_BALANCE - 50 * 19);
+
+        run_to_block(6);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, ALICE), vec![(ASSET_ID, 25)]);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET2, BOB), vec![(ASSET_ID, 25)]);
+
+        assert_noop!(
+            TablescoreModule::cancel_reward_stream(Origin::signed(BOB), 0),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(TablescoreModule::cancel_reward_stream(Origin::signed(CAROL), 0));
+        assert_eq!(tablescore_events().pop(), Some(RawEvent::StreamClosed(0, CAROL, 50 * 18)));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &CAROL), INITIAL_BALANCE - 50);
+        assert_eq!(TablescoreModule::streams_due(11), vec![]);
+
+        run_to_block(11);
+        assert_eq!(TablescoreModule::pending_reward(table_id, TARGET1, ALICE), vec![(ASSET_ID, 25)]);
+        assert_noop!(
+            TablescoreModule::cancel_reward_stream(Origin::ROOT, 0),
+            Error::<Test>::StreamNotFound
+        );
+    });
+}
+
+#[test]
+fn reward_stream_schedule() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+        let create = |recipient, period, end| {
+            TablescoreModule::create_reward_stream(Origin::signed(CAROL), table_id, recipient, 10, period, end)
+        };
+
+        assert_noop!(create(StreamRecipient::Target(TARGET1), 5, 5), Error::<Test>::InvalidStreamSchedule);
+        assert_noop!(create(StreamRecipient::Target(TARGET1), 0, 5), Error::<Test>::InvalidStreamSchedule);
+        assert_noop!(
+            create(StreamRecipient::Target(TARGET1), u64::max_value(), u64::max_value()),
+            Error::<Test>::InvalidStreamSchedule
+        );
+        for amount in [0, MinStreamAmount::get() - 1].iter() {
+            assert_noop!(
+                TablescoreModule::create_reward_stream(
+                    Origin::signed(CAROL),
+                    table_id,
+                    StreamRecipient::Target(TARGET1),
+                    *amount,
+                    5,
+                    6
+                ),
+                Error::<Test>::StreamAmountLow
+            );
+        }
+
+        assert_ok!(create(StreamRecipient::Target(TARGET1), 5, 6));
+        assert_ok!(create(StreamRecipient::Target(TARGET1), 5, 6));
+        assert_ok!(create(StreamRecipient::Head(HeadRewardPolicy::Proportional), 5, 6));
+        assert_noop!(create(StreamRecipient::Target(TARGET1), 5, 6), Error::<Test>::TooManyStreams);
+        // Block 6 has room for two payments only
+        assert_eq!(TablescoreModule::streams_due(6), vec![0, 1]);
+        assert_eq!(TablescoreModule::streams_due(7), vec![2]);
+
+        // Payments to target without votes and to empty head are refunded at the end
+        run_to_block(7);
+        for stream_id in 0..3 {
+            assert_eq!(TablescoreModule::reward_streams(stream_id), None);
+        }
+        assert_eq!(Assets::free_balance(&ASSET_ID, &CAROL), INITIAL_BALANCE);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &TablescoreModule::table_wallet(table_id)), 0);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &TablescoreModule::table_wallet(table_id)), 0);
+    });
+}
+
+#[test]
+fn stream_escrow_of_legacy_table() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+        // Tables of the first release keep tokens on account of their creator
+        Tables::<Test>::mutate(table_id, |info| info.as_mut().map(|info| info.wallet = ALICE));
+
+        assert_ok!(TablescoreModule::create_reward_stream(
+            Origin::signed(CAROL),
+            table_id,
+            StreamRecipient::Target(TARGET1),
+            10,
+            5,
+            19
+        ));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &ALICE), INITIAL_BALANCE);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &ALICE), 30);
+
+        assert_noop!(
+            TablescoreModule::cancel_reward_stream(Origin::signed(ALICE), 0),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(TablescoreModule::cancel_reward_stream(Origin::ROOT, 0));
+        assert_eq!(tablescore_events().pop(), Some(RawEvent::StreamClosed(0, CAROL, 30)));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &CAROL), INITIAL_BALANCE);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &ALICE), 0);
+    });
+}
+
+#[test]
+fn claim_all() {
+    new_test_ext().execute_with(|| {
+        let first = create_table();
+        let second = create_table();
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), first, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), first, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), first, 100, TARGET2));
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), second, 100, TARGET1));
+        assert_eq!(
+            TablescoreModule::votes_by_account(ALICE, first).into_iter().collect::<Vec<_>>(),
+            vec![TARGET1, TARGET2]
+        );
+
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), first, 100, TARGET1, None));
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), first, 40, TARGET2, None));
+        assert_ok!(TablescoreModule::append_reward(
+            Origin::signed(CAROL),
+            second,
+            30,
+            TARGET1,
+            Some(OTHER_ASSET_ID)
+        ));
+
+        // Repeated table is claimed once
+        assert_ok!(TablescoreModule::claim_all(Origin::signed(ALICE), vec![first, second, first]));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &ALICE), INITIAL_BALANCE - 300 + 50 + 40);
+        assert_eq!(Assets::free_balance(&OTHER_ASSET_ID, &ALICE), INITIAL_BALANCE + 30);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &TablescoreModule::table_wallet(first)), 50);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &TablescoreModule::table_wallet(first)), 0);
+        assert_eq!(Assets::reserved_balance(&OTHER_ASSET_ID, &TablescoreModule::table_wallet(second)), 0);
+        assert_eq!(Assets::free_balance(&OTHER_ASSET_ID, &TablescoreModule::table_wallet(second)), 0);
+        assert_eq!(TablescoreModule::pending_reward(first, TARGET1, ALICE), vec![]);
+        assert_eq!(TablescoreModule::pending_reward(first, TARGET1, BOB), vec![(ASSET_ID, 50)]);
+
+        let paid = tablescore_events()
+            .into_iter()
+            .filter(|event| match event {
+                RawEvent::RewardPaid(..) => true,
+                _ => false,
+            })
+            .count();
+        assert_eq!(paid, 3);
+
+        assert_ok!(TablescoreModule::claim_all(Origin::signed(ALICE), vec![first, second]));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &ALICE), INITIAL_BALANCE - 300 + 50 + 40);
+
+        assert_ok!(TablescoreModule::cancel(Origin::signed(ALICE), first, TARGET2));
+        assert_ok!(TablescoreModule::cancel(Origin::signed(ALICE), second, TARGET1));
+        assert_eq!(
+            TablescoreModule::votes_by_account(ALICE, first).into_iter().collect::<Vec<_>>(),
+            vec![TARGET1]
+        );
+        assert!(TablescoreModule::votes_by_account(ALICE, second).is_empty());
+    });
+}
+
+#[test]
+fn claim_all_shared_wallet() {
+    new_test_ext().execute_with(|| {
+        let first = create_table();
+        let second = create_table();
+        // Tables of the first release keep tokens on account of their creator
+        for table_id in vec![first, second] {
+            Tables::<Test>::mutate(table_id, |info| info.as_mut().map(|info| info.wallet = ALICE));
+            assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET1));
+            assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 100, TARGET1, None));
+        }
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &ALICE), 200);
+
+        // Wallet can pay for either table, but not for both
+        Assets::unreserve(&ASSET_ID, &ALICE, 50);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &ALICE), 150);
+        assert_noop!(
+            TablescoreModule::claim_all(Origin::signed(BOB), vec![first, second]),
+            Error::<Test>::WalletBalanceLow
+        );
+
+        assert_ok!(Assets::reserve(&ASSET_ID, &ALICE, 50));
+        assert_ok!(TablescoreModule::claim_all(Origin::signed(BOB), vec![first, second]));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &BOB), INITIAL_BALANCE - 200 + 200);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &ALICE), 0);
+    });
+}
+
+#[test]
+fn votes_of_account() {
+    new_test_ext().execute_with(|| {
+        let first = create_table();
+        let second = create_table();
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), first, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), first, 200, TARGET2));
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), second, 300, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), second, 400, TARGET2));
+        assert_ok!(TablescoreModule::unvote(Origin::signed(ALICE), first, 50, TARGET2));
+
+        let sorted_votes = |voter| {
+            let mut votes = TablescoreModule::votes_of_account(voter);
+            votes.sort();
+            votes
+        };
+        assert_eq!(
+            sorted_votes(ALICE),
+            vec![(first, TARGET1, 100), (first, TARGET2, 150), (second, TARGET1, 300)]
+        );
+        assert_eq!(sorted_votes(BOB), vec![(second, TARGET2, 400)]);
+
+        assert_ok!(TablescoreModule::cancel(Origin::signed(ALICE), first, TARGET1));
+        assert_ok!(TablescoreModule::cancel(Origin::signed(ALICE), second, TARGET1));
+        assert_eq!(sorted_votes(ALICE), vec![(first, TARGET2, 150)]);
+        assert_eq!(sorted_votes(CAROL), vec![]);
+    });
+}
+
+#[test]
+fn freeze_table() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1));
+        assert_noop!(
+            TablescoreModule::freeze_table(Origin::signed(BOB), table_id),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(TablescoreModule::freeze_table(Origin::signed(ALICE), table_id));
+        assert_eq!(
+            tablescore_events().pop(),
+            Some(RawEvent::TableStateChanged(table_id, TableState::Frozen))
+        );
+        assert_eq!(TablescoreModule::tables(table_id).map(|info| info.state), Some(TableState::Frozen));
+
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET1),
+            Error::<Test>::TableFrozen
+        );
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 10, TARGET1, None));
+        assert_ok!(TablescoreModule::unvote(Origin::signed(ALICE), table_id, 50, TARGET1));
+        assert_ok!(TablescoreModule::pop_reward(Origin::signed(ALICE), table_id, TARGET1));
+        assert_eq!(Assets::free_balance(&ASSET_ID, &ALICE), INITIAL_BALANCE - 50 + 10);
+
+        assert_ok!(TablescoreModule::unfreeze_table(Origin::ROOT, table_id));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET1));
+    });
+}
+
+#[test]
+fn destroy_table() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+        let wallet = TablescoreModule::table_wallet(table_id);
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 300, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 700, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET2));
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 5, TARGET1, None));
+        assert_ok!(TablescoreModule::create_reward_stream(
+            Origin::signed(CAROL),
+            table_id,
+            StreamRecipient::Target(TARGET1),
+            40,
+            10,
+            35
+        ));
+        assert_eq!(TablescoreModule::table_streams(table_id), vec![0]);
+
+        assert_noop!(
+            TablescoreModule::destroy_table(Origin::signed(BOB), table_id),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(TablescoreModule::destroy_table(Origin::signed(ALICE), table_id));
+        assert_eq!(TablescoreModule::destroy_queue(), vec![table_id]);
+
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET2),
+            Error::<Test>::TableDestroying
+        );
+        assert_noop!(
+            TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 5, TARGET1, None),
+            Error::<Test>::TableDestroying
+        );
+        assert_noop!(
+            TablescoreModule::create_reward_stream(
+                Origin::signed(CAROL),
+                table_id,
+                StreamRecipient::Target(TARGET1),
+                40,
+                10,
+                35
+            ),
+            Error::<Test>::TableDestroying
+        );
+        assert_noop!(
+            TablescoreModule::unfreeze_table(Origin::signed(ALICE), table_id),
+            Error::<Test>::TableDestroying
+        );
+        assert_noop!(
+            TablescoreModule::set_head_len(Origin::signed(ALICE), table_id, 1),
+            Error::<Test>::TableDestroying
+        );
+
+        // Two votes of TARGET1 are cancelled in the first block
+        run_to_block(2);
+        assert!(TablescoreModule::tables(table_id).is_some());
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &ALICE), 100);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &BOB), 0);
+
+        run_to_block(3);
+        assert_eq!(tablescore_events().pop(), Some(RawEvent::TableDestroyed(table_id)));
+        assert_eq!(TablescoreModule::tables(table_id), None);
+        assert!(TablescoreModule::scores(table_id).is_empty());
+        assert!(TablescoreModule::votes_by_account(ALICE, table_id).is_empty());
+        assert!(TablescoreModule::table_streams(table_id).is_empty());
+        assert!(TablescoreModule::destroy_queue().is_empty());
+        assert_eq!(TablescoreModule::reward_streams(0), None);
+        assert_eq!(TablescoreModule::streams_due(11), vec![]);
+
+        // Rewards are paid to voters, escrow is refunded and dust goes to owner
+        assert_eq!(Assets::free_balance(&ASSET_ID, &ALICE), INITIAL_BALANCE + 1 + 1);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &BOB), INITIAL_BALANCE + 3);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &CAROL), INITIAL_BALANCE - 5);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &wallet), 0);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &wallet), 0);
+        assert_eq!(TablescoreModule::dust(table_id, ASSET_ID), 0);
+    });
+}
+
+#[test]
+fn destroy_table_with_unpaid_reward() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+        let wallet = TablescoreModule::table_wallet(table_id);
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 10, TARGET1, None));
+        // Reward tokens left the wallet, so the reward can't be paid
+        Assets::unreserve(&ASSET_ID, &wallet, 10);
+        assert_ok!(Assets::make_transfer(&ASSET_ID, &wallet, &CAROL, 10));
+        assert_noop!(
+            TablescoreModule::cancel(Origin::signed(BOB), table_id, TARGET1),
+            Error::<Test>::WalletBalanceLow
+        );
+        assert_ok!(TablescoreModule::destroy_table(Origin::signed(ALICE), table_id));
+
+        System::set_block_number(1);
+        assert!(TablescoreModule::on_initialize(1) > 0);
+        let mut events = tablescore_events();
+        assert_eq!(events.pop(), Some(RawEvent::TableDestroyed(table_id)));
+        assert!(events.contains(&RawEvent::RewardToDust(table_id, TARGET1, BOB, ASSET_ID, 10)));
+        assert_eq!(TablescoreModule::tables(table_id), None);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &BOB), 0);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &BOB), INITIAL_BALANCE);
+
+        System::set_block_number(2);
+        assert_eq!(TablescoreModule::on_initialize(2), 0);
+    });
+}
+
+#[test]
+fn destroy_table_with_candidates() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+        let wallet = TablescoreModule::table_wallet(table_id);
+        let candidates = || {
+            [TARGET1, TARGET2, TARGET3]
+                .iter()
+                .filter(|target| TablescoreModule::candidate(table_id, **target).is_some())
+                .count()
+        };
+
+        assert_ok!(TablescoreModule::set_registration(Origin::signed(ALICE), table_id, Some(50)));
+        for target in [TARGET1, TARGET2, TARGET3].iter() {
+            assert_ok!(TablescoreModule::register_target(Origin::signed(ALICE), table_id, *target));
+        }
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET2));
+
+        // Reward that can't be paid doesn't stop deregistration halfway
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 10, TARGET2, None));
+        Assets::unreserve(&ASSET_ID, &wallet, 10);
+        assert_ok!(Assets::make_transfer(&ASSET_ID, &wallet, &CAROL, 10));
+        assert_ok!(TablescoreModule::deregister_target(Origin::signed(ALICE), table_id, TARGET2));
+        let mut events = tablescore_events();
+        assert_eq!(events.pop(), Some(RawEvent::TargetDeregistered(table_id, TARGET2)));
+        assert!(events.contains(&RawEvent::RewardToDust(table_id, TARGET2, CAROL, ASSET_ID, 10)));
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &CAROL), 0);
+        assert_eq!(candidates(), 2);
+
+        // Candidacy deposits are returned out of the same budget as votes
+        assert_ok!(TablescoreModule::destroy_table(Origin::signed(ALICE), table_id));
+        run_to_block(2);
+        assert_eq!(Assets::reserved_balance(&ASSET_ID, &BOB), 0);
+        assert_eq!(candidates(), 1);
+        assert!(TablescoreModule::tables(table_id).is_some());
+
+        run_to_block(3);
+        assert_eq!(candidates(), 0);
+        assert_eq!(TablescoreModule::tables(table_id), None);
+        assert_eq!(Assets::reserved_balance(&DEPOSIT_ASSET_ID, &ALICE), 0);
+    });
+}
+
+#[test]
+fn table_administration() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 300, TARGET1));
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 200, TARGET2));
+
+        assert_noop!(
+            TablescoreModule::set_name(Origin::signed(BOB), table_id, None),
+            Error::<Test>::NotOwner
+        );
+        assert_noop!(
+            TablescoreModule::set_admins(Origin::signed(BOB), table_id, vec![BOB]),
+            Error::<Test>::NotOwner
+        );
+        assert_noop!(
+            TablescoreModule::set_admins(Origin::signed(ALICE), table_id, vec![BOB, CAROL, ALICE]),
+            Error::<Test>::TooManyAdmins
+        );
+        assert_ok!(TablescoreModule::set_admins(Origin::signed(ALICE), table_id, vec![BOB, BOB]));
+        assert_eq!(tablescore_events().pop(), Some(RawEvent::AdminsSet(table_id, vec![BOB])));
+        assert!(TablescoreModule::table_admins(table_id).contains(&BOB));
+
+        assert_ok!(TablescoreModule::set_name(Origin::signed(BOB), table_id, Some(b"top".to_vec())));
+        assert_eq!(tablescore_events().pop(), Some(RawEvent::TableRenamed(table_id, Some(b"top".to_vec()))));
+        assert_eq!(TablescoreModule::tables(table_id).unwrap().name, Some(b"top".to_vec()));
+        assert_noop!(
+            TablescoreModule::set_name(Origin::signed(BOB), table_id, Some(vec![0; 17])),
+            Error::<Test>::NameTooLong
+        );
+
+        assert_noop!(
+            TablescoreModule::set_head_len(Origin::signed(BOB), table_id, HEAD_COUNT + 1),
+            Error::<Test>::HeadTooLong
+        );
+        assert_ok!(TablescoreModule::set_head_len(Origin::signed(BOB), table_id, 1));
+        assert_eq!(TablescoreModule::head(table_id), vec![TARGET1]);
+        assert_eq!(
+            tablescore_events().pop(),
+            Some(RawEvent::HeadChanged(table_id, vec![], vec![TARGET2]))
+        );
+
+        // Admins can't hand table to anyone
+        assert_noop!(
+            TablescoreModule::transfer_ownership(Origin::signed(BOB), table_id, BOB),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(TablescoreModule::transfer_ownership(Origin::signed(ALICE), table_id, CAROL));
+        assert_eq!(
+            tablescore_events().pop(),
+            Some(RawEvent::OwnershipTransferred(table_id, ALICE, CAROL))
+        );
+        assert_noop!(
+            TablescoreModule::freeze_table(Origin::signed(ALICE), table_id),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(TablescoreModule::freeze_table(Origin::signed(CAROL), table_id));
+        assert_ok!(TablescoreModule::set_head_len(Origin::signed(BOB), table_id, HEAD_COUNT));
+
+        assert_ok!(TablescoreModule::transfer_ownership(Origin::ROOT, table_id, ALICE));
+        assert_eq!(TablescoreModule::tables(table_id).unwrap().owner, ALICE);
+    });
+}
+
+#[test]
+fn table_deposit() {
+    new_test_ext().execute_with(|| {
+        let first = create_table();
+        assert_eq!(Assets::reserved_balance(&DEPOSIT_ASSET_ID, &ALICE), TABLE_DEPOSIT);
+        assert_eq!(TablescoreModule::table_deposit(first), Some((ALICE, DEPOSIT_ASSET_ID, TABLE_DEPOSIT)));
+        assert_eq!(TablescoreModule::created_tables(ALICE), 1);
+
+        create_table();
+        create_table();
+        assert_noop!(
+            TablescoreModule::create_table(Origin::signed(ALICE), ASSET_ID, HEAD_COUNT, None, None),
+            Error::<Test>::TooManyTables
+        );
+
+        Assets::set_free_balance(&DEPOSIT_ASSET_ID, &BOB, TABLE_DEPOSIT - 1);
+        assert_noop!(
+            TablescoreModule::create_table(Origin::signed(BOB), ASSET_ID, HEAD_COUNT, None, None),
+            Error::<Test>::DepositBalanceLow
+        );
+
+        // Deposit goes back to creator when the new owner destroys table
+        assert_ok!(TablescoreModule::transfer_ownership(Origin::signed(ALICE), first, BOB));
+        assert_ok!(TablescoreModule::destroy_table(Origin::signed(BOB), first));
+        run_to_block(2);
+        assert_eq!(TablescoreModule::table_deposit(first), None);
+        assert_eq!(TablescoreModule::created_tables(ALICE), 2);
+        assert_eq!(Assets::reserved_balance(&DEPOSIT_ASSET_ID, &ALICE), 2 * TABLE_DEPOSIT);
+        assert_eq!(Assets::free_balance(&DEPOSIT_ASSET_ID, &ALICE), INITIAL_BALANCE - 2 * TABLE_DEPOSIT);
+        assert_eq!(Assets::reserved_balance(&DEPOSIT_ASSET_ID, &BOB), 0);
+
+        create_table();
+    });
+}
+
+#[test]
+fn target_registration() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET1));
+        assert_noop!(
+            TablescoreModule::register_target(Origin::signed(BOB), table_id, TARGET1),
+            Error::<Test>::RegistrationDisabled
+        );
+        assert_noop!(
+            TablescoreModule::set_registration(Origin::signed(BOB), table_id, Some(50)),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(TablescoreModule::set_registration(Origin::signed(ALICE), table_id, Some(50)));
+        assert_eq!(tablescore_events().pop(), Some(RawEvent::RegistrationSet(table_id, Some(50))));
+
+        // Targets voted before registration was required are unregistered too
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET1),
+            Error::<Test>::TargetNotRegistered
+        );
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET2),
+            Error::<Test>::TargetNotRegistered
+        );
+
+        // BOB is the account of TARGET1
+        assert_noop!(
+            TablescoreModule::register_target(Origin::signed(CAROL), table_id, TARGET1),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(TablescoreModule::register_target(Origin::signed(BOB), table_id, TARGET1));
+        assert_eq!(tablescore_events().pop(), Some(RawEvent::TargetRegistered(table_id, TARGET1, BOB)));
+        assert_eq!(Assets::reserved_balance(&DEPOSIT_ASSET_ID, &BOB), 50);
+        assert_noop!(
+            TablescoreModule::register_target(Origin::signed(ALICE), table_id, TARGET1),
+            Error::<Test>::TargetAlreadyRegistered
+        );
+        assert_ok!(TablescoreModule::register_target(Origin::signed(ALICE), table_id, TARGET3));
+        assert_eq!(TablescoreModule::candidate(table_id, TARGET3), Some((ALICE, DEPOSIT_ASSET_ID, 50)));
+
+        assert_ok!(TablescoreModule::vote(Origin::signed(ALICE), table_id, 200, TARGET1));
+        assert_ok!(TablescoreModule::append_reward(Origin::signed(CAROL), table_id, 30, TARGET1, None));
+
+        // Deregistration cancels votes with payout of rewards
+        assert_noop!(
+            TablescoreModule::deregister_target(Origin::signed(CAROL), table_id, TARGET1),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(TablescoreModule::deregister_target(Origin::signed(BOB), table_id, TARGET1));
+        assert_eq!(tablescore_events().pop(), Some(RawEvent::TargetDeregistered(table_id, TARGET1)));
+        assert_eq!(TablescoreModule::score_of(table_id, TARGET1), None);
+        assert_eq!(TablescoreModule::candidate(table_id, TARGET1), None);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &ALICE), INITIAL_BALANCE + 20);
+        assert_eq!(Assets::free_balance(&ASSET_ID, &CAROL), INITIAL_BALANCE - 30 + 10);
+        assert_eq!(Assets::reserved_balance(&DEPOSIT_ASSET_ID, &BOB), 0);
+        assert_noop!(
+            TablescoreModule::deregister_target(Origin::ROOT, table_id, TARGET1),
+            Error::<Test>::TargetNotRegistered
+        );
+
+        assert_ok!(TablescoreModule::set_registration(Origin::ROOT, table_id, None));
+        assert_ok!(TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET2));
+    });
+}
+
+#[test]
+fn voter_policy() {
+    new_test_ext().execute_with(|| {
+        let table_id = create_table();
+
+        assert_noop!(
+            TablescoreModule::set_voter_policy(Origin::signed(BOB), table_id, VoterPolicy::AllowList),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(TablescoreModule::set_voter_policy(Origin::signed(ALICE), table_id, VoterPolicy::AllowList));
+        assert_eq!(
+            tablescore_events().pop(),
+            Some(RawEvent::VoterPolicySet(table_id, VoterPolicy::AllowList))
+        );
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(ALICE), table_id, 100, TARGET1),
+            Error::<Test>::VoterNotAllowed
+        );
+
+        assert_noop!(
+            TablescoreModule::allow_voters(Origin::signed(CAROL), table_id, vec![CAROL]),
+            Error::<Test>::NotOwner
+        );
+        assert_ok!(TablescoreModule::allow_voters(Origin::signed(ALICE), table_id, vec![CAROL]));
+        assert_eq!(tablescore_events().pop(), Some(RawEvent::VotersAllowed(table_id, vec![CAROL])));
+        assert!(TablescoreModule::is_allowed_voter(table_id, CAROL));
+        assert_ok!(TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET1));
+
+        // Votes given before stay until voter takes them back
+        assert_ok!(TablescoreModule::disallow_voters(Origin::signed(ALICE), table_id, vec![CAROL]));
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET1),
+            Error::<Test>::VoterNotAllowed
+        );
+        assert_ok!(TablescoreModule::unvote(Origin::signed(CAROL), table_id, 50, TARGET1));
+
+        assert_ok!(TablescoreModule::set_voter_policy(Origin::ROOT, table_id, VoterPolicy::Members));
+        assert_noop!(
+            TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET1),
+            Error::<Test>::VoterNotAllowed
+        );
+        assert_ok!(TablescoreModule::vote(Origin::signed(BOB), table_id, 100, TARGET1));
+
+        assert_ok!(TablescoreModule::set_voter_policy(Origin::signed(ALICE), table_id, VoterPolicy::Open));
+        assert_ok!(TablescoreModule::vote(Origin::signed(CAROL), table_id, 100, TARGET1));
+    });
+}
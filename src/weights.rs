@@ -0,0 +1,177 @@
+//! Weights for tablescore dispatchables.
+//!
+//! Dispatchables are charged for the biggest table allowed by
+//! `Trait::MaxTargetsPerTable`. Every reward period of target is kept for
+//! checkpoint of some voter, so they're charged for `Trait::MaxVotersPerTarget`
+//! reward periods.
+//!
+//! Weight functions take the components used in `benchmarking.rs`:
+//! `n` is the length of table name, the count of tables in claim,
+//! the count of table admins or the count of voters in allow-list change,
+//! `t` is the count of targets in table, or voted in every claimed table,
+//! `v` is the count of voters of target,
+//! `h` is the count of targets in table head, `p` is the count of reward
+//! periods of target and `a` is the count of reward assets of target.
+//! In `migrate_table` `t` is the count of targets and `v` is the count of
+//! votes in the migrated table. In `remove_table` `s` is the count of reward
+//! streams of destroyed table.
+//! Values of `()` implementation are to be regenerated with `benchmark`
+//! command of node for the hardware of the chain.
+
+use frame_support::weights::Weight;
+
+pub trait WeightInfo {
+    fn create_table(n: u32) -> Weight;
+    fn vote(t: u32, p: u32, a: u32) -> Weight;
+    fn unvote(t: u32, p: u32, a: u32) -> Weight;
+    fn cancel(t: u32, p: u32, a: u32) -> Weight;
+    fn append_reward(t: u32, p: u32, a: u32) -> Weight;
+    fn append_head_reward(t: u32, h: u32, p: u32, a: u32) -> Weight;
+    fn pop_reward(t: u32, p: u32, a: u32) -> Weight;
+    fn claim_all(n: u32, t: u32, p: u32, a: u32) -> Weight;
+    fn sweep_dust() -> Weight;
+    fn create_reward_stream() -> Weight;
+    fn cancel_reward_stream() -> Weight;
+    fn set_table_state() -> Weight;
+    fn transfer_ownership() -> Weight;
+    fn set_admins(n: u32) -> Weight;
+    fn set_name(n: u32) -> Weight;
+    fn set_head_len(t: u32) -> Weight;
+    fn set_registration() -> Weight;
+    fn register_target() -> Weight;
+    fn deregister_target(t: u32, v: u32) -> Weight;
+    fn set_voter_policy() -> Weight;
+    fn allow_voters(n: u32) -> Weight;
+    fn disallow_voters(n: u32) -> Weight;
+    fn migrate_table(t: u32, v: u32) -> Weight;
+    fn remove_table(s: u32) -> Weight;
+}
+
+impl WeightInfo for () {
+    fn create_table(n: u32) -> Weight {
+        (20_000_000 as Weight).saturating_add((2_000 as Weight).saturating_mul(n as Weight))
+    }
+
+    fn vote(t: u32, p: u32, a: u32) -> Weight {
+        (60_000_000 as Weight)
+            .saturating_add((90_000 as Weight).saturating_mul(t as Weight))
+            .saturating_add((40_000 as Weight).saturating_mul(p as Weight))
+            .saturating_add((30_000_000 as Weight).saturating_mul(a as Weight))
+    }
+
+    fn unvote(t: u32, p: u32, a: u32) -> Weight {
+        (60_000_000 as Weight)
+            .saturating_add((90_000 as Weight).saturating_mul(t as Weight))
+            .saturating_add((40_000 as Weight).saturating_mul(p as Weight))
+            .saturating_add((30_000_000 as Weight).saturating_mul(a as Weight))
+    }
+
+    fn cancel(t: u32, p: u32, a: u32) -> Weight {
+        (60_000_000 as Weight)
+            .saturating_add((90_000 as Weight).saturating_mul(t as Weight))
+            .saturating_add((40_000 as Weight).saturating_mul(p as Weight))
+            .saturating_add((30_000_000 as Weight).saturating_mul(a as Weight))
+    }
+
+    fn append_reward(t: u32, p: u32, a: u32) -> Weight {
+        (50_000_000 as Weight)
+            .saturating_add((50_000 as Weight).saturating_mul(t as Weight))
+            .saturating_add((40_000 as Weight).saturating_mul(p as Weight))
+            .saturating_add((100_000 as Weight).saturating_mul(a as Weight))
+    }
+
+    fn append_head_reward(t: u32, h: u32, p: u32, a: u32) -> Weight {
+        (50_000_000 as Weight)
+            .saturating_add((50_000 as Weight).saturating_mul(t as Weight))
+            .saturating_add((25_000_000 as Weight).saturating_mul(h as Weight))
+            .saturating_add((40_000 as Weight).saturating_mul(p as Weight))
+            .saturating_add((100_000 as Weight).saturating_mul(a as Weight))
+    }
+
+    fn pop_reward(t: u32, p: u32, a: u32) -> Weight {
+        (50_000_000 as Weight)
+            .saturating_add((90_000 as Weight).saturating_mul(t as Weight))
+            .saturating_add((40_000 as Weight).saturating_mul(p as Weight))
+            .saturating_add((30_000_000 as Weight).saturating_mul(a as Weight))
+    }
+
+    fn claim_all(n: u32, t: u32, p: u32, a: u32) -> Weight {
+        let pairs = (n as Weight).saturating_mul(t as Weight);
+        (20_000_000 as Weight)
+            .saturating_add((30_000_000 as Weight).saturating_mul(n as Weight).saturating_mul(a as Weight))
+            .saturating_add(
+                (50_000_000 as Weight)
+                    .saturating_add((40_000 as Weight).saturating_mul(p as Weight))
+                    .saturating_add((100_000 as Weight).saturating_mul(a as Weight))
+                    .saturating_mul(pairs),
+            )
+    }
+
+    fn sweep_dust() -> Weight {
+        40_000_000 as Weight
+    }
+
+    fn create_reward_stream() -> Weight {
+        60_000_000 as Weight
+    }
+
+    fn cancel_reward_stream() -> Weight {
+        50_000_000 as Weight
+    }
+
+    fn set_table_state() -> Weight {
+        30_000_000 as Weight
+    }
+
+    fn transfer_ownership() -> Weight {
+        30_000_000 as Weight
+    }
+
+    fn set_admins(n: u32) -> Weight {
+        (30_000_000 as Weight).saturating_add((500_000 as Weight).saturating_mul(n as Weight))
+    }
+
+    fn set_name(n: u32) -> Weight {
+        (30_000_000 as Weight).saturating_add((2_000 as Weight).saturating_mul(n as Weight))
+    }
+
+    fn set_head_len(t: u32) -> Weight {
+        (40_000_000 as Weight).saturating_add((50_000 as Weight).saturating_mul(t as Weight))
+    }
+
+    fn set_registration() -> Weight {
+        30_000_000 as Weight
+    }
+
+    fn register_target() -> Weight {
+        50_000_000 as Weight
+    }
+
+    fn deregister_target(t: u32, v: u32) -> Weight {
+        (50_000_000 as Weight)
+            .saturating_add((90_000 as Weight).saturating_mul(t as Weight))
+            .saturating_add((60_000_000 as Weight).saturating_mul(v as Weight))
+    }
+
+    fn set_voter_policy() -> Weight {
+        30_000_000 as Weight
+    }
+
+    fn allow_voters(n: u32) -> Weight {
+        (30_000_000 as Weight).saturating_add((5_000_000 as Weight).saturating_mul(n as Weight))
+    }
+
+    fn disallow_voters(n: u32) -> Weight {
+        (30_000_000 as Weight).saturating_add((5_000_000 as Weight).saturating_mul(n as Weight))
+    }
+
+    fn migrate_table(t: u32, v: u32) -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add((20_000_000 as Weight).saturating_mul(t as Weight))
+            .saturating_add((30_000_000 as Weight).saturating_mul(v as Weight))
+    }
+
+    fn remove_table(s: u32) -> Weight {
+        (100_000_000 as Weight).saturating_add((50_000_000 as Weight).saturating_mul(s as Weight))
+    }
+}